@@ -0,0 +1,120 @@
+//! Background threads connected to their parent by an inproc pipe.
+
+use std::ops::Deref;
+use std::thread::{self, JoinHandle};
+
+use crate::pipe::{recv_reply, REPLY_POLL_INTERVAL};
+use crate::{Context, Error, Result, Socket, DONTWAIT, POLLOUT};
+
+/// A background thread talking to its parent over a `PAIR` socket.
+///
+/// This follows CZMQ's `zactor` pattern: the thread runs a function
+/// which receives its end of the pipe, and the parent sends commands to
+/// it through the `Actor`, which dereferences to the parent's end of
+/// the pipe. `spawn()` only returns once the thread has started and
+/// acknowledged the pipe with a `READY` message.
+///
+/// The actor is asked to stop by sending it `Actor::TERM`, which
+/// happens when the `Actor` is dropped or terminated. The function must
+/// handle this by returning; until it does, dropping the `Actor`
+/// blocks. If the pipe is full, the command waits for the actor to
+/// catch up, or to stop on its own.
+///
+/// ```
+/// # fn main() -> zmq::Result<()> {
+/// let ctx = zmq::Context::new();
+/// let echo = zmq::Actor::spawn(&ctx, |pipe| loop {
+///     let msg = pipe.recv_msg(0)?;
+///     if &msg[..] == zmq::Actor::TERM.as_bytes() {
+///         return Ok(());
+///     }
+///     pipe.send(msg, 0)?;
+/// })?;
+/// echo.send("hello", 0)?;
+/// assert_eq!(echo.recv_bytes(0)?, b"hello");
+/// echo.terminate()
+/// # }
+/// ```
+pub struct Actor {
+    pipe: Socket,
+    thread: Option<JoinHandle<Result<()>>>,
+}
+
+impl Actor {
+    /// The command asking an actor to stop.
+    pub const TERM: &'static str = "$TERM";
+
+    /// Run `actor` on a background thread, passing it its end of the
+    /// pipe.
+    ///
+    /// If the thread stops before acknowledging the pipe, this fails
+    /// with the error it stopped with.
+    pub fn spawn<F>(ctx: &Context, actor: F) -> Result<Actor>
+    where
+        F: FnOnce(Socket) -> Result<()> + Send + 'static,
+    {
+        let (pipe, child) = ctx.pipe()?;
+
+        let thread = thread::Builder::new()
+            .name("zmq-actor".into())
+            .spawn(move || {
+                child.send("READY", 0)?;
+                actor(child)
+            })
+            .map_err(|_| Error::EAGAIN)?;
+        let actor = Actor {
+            pipe,
+            thread: Some(thread),
+        };
+        match recv_reply(&actor.pipe, actor.thread.as_ref().unwrap()) {
+            Ok(_) => Ok(actor),
+            // The thread stopped without acknowledging the pipe, so its
+            // result tells why.
+            Err(Error::EAGAIN) => Err(actor.terminate().err().unwrap_or(Error::EFAULT)),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Stop the actor, and wait for its thread to finish.
+    ///
+    /// Returns the error the actor's function failed with, if any.
+    pub fn terminate(mut self) -> Result<()> {
+        self.terminate_inner()
+    }
+
+    fn terminate_inner(&mut self) -> Result<()> {
+        let thread = match self.thread.take() {
+            Some(thread) => thread,
+            None => return Ok(()),
+        };
+        // The actor may already have stopped, in which case there is
+        // nobody to receive the command. Otherwise, it may not have
+        // caught up with earlier messages, so wait for room in the pipe
+        // rather than dropping the command.
+        while !thread.is_finished() {
+            match self.pipe.send(Actor::TERM, DONTWAIT) {
+                Err(Error::EAGAIN) => {
+                    if self.pipe.poll(POLLOUT, REPLY_POLL_INTERVAL).is_err() {
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        }
+        thread.join().unwrap_or(Err(Error::EFAULT))
+    }
+}
+
+impl Deref for Actor {
+    type Target = Socket;
+
+    fn deref(&self) -> &Socket {
+        &self.pipe
+    }
+}
+
+impl Drop for Actor {
+    fn drop(&mut self) {
+        let _ = self.terminate_inner();
+    }
+}
@@ -0,0 +1,435 @@
+//! Asynchronous sockets on top of the tokio or async-io reactors.
+
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::io;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::pin::Pin;
+use std::result;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context as TaskContext, Poll};
+
+#[cfg(feature = "async-io")]
+use async_io::Async;
+#[cfg(feature = "tokio")]
+use tokio::io::unix::AsyncFd;
+
+use crate::proxy::recv_parts;
+use crate::{
+    io_error_to_zmq, Context, Error, Message, MonitorEvent, PollEvents, Result, Socket, DONTWAIT,
+    PAIR, POLLIN, POLLOUT, SNDMORE,
+};
+
+/// The `ZMQ_FD` of a socket, which is owned by libzmq.
+struct SignalFd(RawFd);
+
+impl AsRawFd for SignalFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0
+    }
+}
+
+impl AsFd for SignalFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        // `AsyncSocket` keeps the socket, and thus the descriptor,
+        // alive for as long as the registration.
+        unsafe { BorrowedFd::borrow_raw(self.0) }
+    }
+}
+
+/// The registration of a `ZMQ_FD` with a reactor.
+enum Reactor {
+    #[cfg(feature = "tokio")]
+    Tokio(AsyncFd<SignalFd>),
+    #[cfg(feature = "async-io")]
+    AsyncIo(Async<SignalFd>),
+}
+
+impl Reactor {
+    /// Wait for the descriptor to signal a change of the socket's
+    /// state, and consume that signal.
+    fn poll_signal(&self, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        match *self {
+            #[cfg(feature = "tokio")]
+            Reactor::Tokio(ref fd) => match fd.poll_read_ready(cx) {
+                Poll::Ready(Ok(mut guard)) => {
+                    guard.clear_ready();
+                    Poll::Ready(Ok(()))
+                }
+                Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+                Poll::Pending => Poll::Pending,
+            },
+            #[cfg(feature = "async-io")]
+            Reactor::AsyncIo(ref fd) => fd.poll_readable(cx),
+        }
+    }
+}
+
+/// A socket driven by an async reactor.
+///
+/// The socket's `ZMQ_FD` is registered with either the tokio reactor
+/// (with the `tokio` feature) or the async-io reactor used by smol and
+/// async-std (with the `async-io` feature); the API is the same for
+/// both. As that descriptor is edge-triggered, all operations re-check
+/// `Socket::get_events()` before waiting for it, so no readiness
+/// change is lost.
+///
+/// All operations are non-blocking; `DONTWAIT` is added to the flags
+/// passed to them. Dropping an operation's future before it completes
+/// cancels it without losing messages, and without sending or receiving
+/// only some parts of a multi-part message.
+///
+/// Like `Socket`, an `AsyncSocket` can be moved to another thread, but
+/// not shared between threads.
+pub struct AsyncSocket {
+    // Declared before `socket` so that the descriptor is deregistered
+    // before the socket closes it.
+    reactor: Reactor,
+    socket: Socket,
+}
+
+impl AsyncSocket {
+    /// Register `socket` with the reactor of the current runtime.
+    ///
+    /// The tokio reactor is used if the `tokio` feature is enabled and
+    /// this is called within a tokio runtime; otherwise, the async-io
+    /// reactor is used if the `async-io` feature is enabled.
+    pub fn new(socket: Socket) -> io::Result<AsyncSocket> {
+        #[cfg(feature = "tokio")]
+        {
+            #[cfg(feature = "async-io")]
+            {
+                if tokio::runtime::Handle::try_current().is_err() {
+                    return AsyncSocket::with_async_io(socket);
+                }
+            }
+            AsyncSocket::with_tokio(socket)
+        }
+        #[cfg(not(feature = "tokio"))]
+        {
+            AsyncSocket::with_async_io(socket)
+        }
+    }
+
+    /// Register `socket` with the tokio reactor.
+    ///
+    /// Fails if not called within a tokio runtime. Like other tokio IO
+    /// types, this panics if the runtime's IO driver is not enabled.
+    #[cfg(feature = "tokio")]
+    pub fn with_tokio(socket: Socket) -> io::Result<AsyncSocket> {
+        if tokio::runtime::Handle::try_current().is_err() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "not called within a tokio runtime",
+            ));
+        }
+        let fd = SignalFd(socket.get_fd().map_err(io::Error::from)?);
+        Ok(AsyncSocket {
+            reactor: Reactor::Tokio(AsyncFd::new(fd)?),
+            socket,
+        })
+    }
+
+    /// Register `socket` with the async-io reactor, as used by smol and
+    /// async-std.
+    #[cfg(feature = "async-io")]
+    pub fn with_async_io(socket: Socket) -> io::Result<AsyncSocket> {
+        let fd = SignalFd(socket.get_fd().map_err(io::Error::from)?);
+        Ok(AsyncSocket {
+            reactor: Reactor::AsyncIo(Async::new(fd)?),
+            socket,
+        })
+    }
+
+    /// Get a reference to the underlying socket, e.g. to set options.
+    pub fn get_ref(&self) -> &Socket {
+        &self.socket
+    }
+
+    /// Deregister the socket from the reactor and return it.
+    pub fn into_inner(self) -> Socket {
+        let AsyncSocket { reactor, socket } = self;
+        drop(reactor);
+        socket
+    }
+
+    /// Poll until the socket is ready for any of `events`, i.e.
+    /// `POLLIN` and/or `POLLOUT`.
+    ///
+    /// When `Poll::Pending` is returned, the task is woken up once the
+    /// state of the socket changes.
+    pub fn poll_events(&self, cx: &mut TaskContext<'_>, events: PollEvents) -> Poll<Result<()>> {
+        loop {
+            if self.socket.get_events()?.intersects(events) {
+                return Poll::Ready(Ok(()));
+            }
+            match self.reactor.poll_signal(cx) {
+                Poll::Ready(Ok(())) => continue,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(io_error_to_zmq(e))),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
+    /// Wait until the socket is ready for any of `events`.
+    pub async fn ready(&self, events: PollEvents) -> Result<()> {
+        poll_fn(|cx| self.poll_events(cx, events)).await
+    }
+
+    /// Run `op` once the socket is ready for `events`, retrying while it
+    /// fails with `EAGAIN`.
+    async fn retry<T, F>(&self, events: PollEvents, mut op: F) -> Result<T>
+    where
+        F: FnMut(&Socket) -> Result<T>,
+    {
+        loop {
+            self.ready(events).await?;
+            match op(&self.socket) {
+                Err(Error::EAGAIN) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// Send a message, see `Socket::send()`.
+    pub async fn send<T>(&self, data: T, flags: i32) -> Result<()>
+    where
+        T: Into<Message>,
+    {
+        let mut msg = data.into();
+        self.retry(POLLOUT, |socket| {
+            socket.send_in_place(&mut msg, flags | DONTWAIT)
+        })
+        .await
+    }
+
+    /// Send a multi-part message, see `Socket::send_multipart()`.
+    ///
+    /// Only sending the first part may have to wait. Once libzmq has
+    /// accepted it, it accepts the remaining parts as well, so these are
+    /// sent without yielding.
+    pub async fn send_multipart<I, T>(&self, iter: I, flags: i32) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Message>,
+    {
+        let mut parts: VecDeque<Message> = iter.into_iter().map(Into::into).collect();
+        let mut first = match parts.pop_front() {
+            Some(first) => first,
+            None => return Ok(()),
+        };
+        let more = if parts.is_empty() { 0 } else { SNDMORE };
+        self.retry(POLLOUT, |socket| {
+            socket.send_in_place(&mut first, flags | more | DONTWAIT)
+        })
+        .await?;
+        self.socket.send_multipart(parts, flags | DONTWAIT)
+    }
+
+    /// Receive a message into `msg`, see `Socket::recv()`.
+    pub async fn recv(&self, msg: &mut Message, flags: i32) -> Result<()> {
+        self.retry(POLLIN, |socket| socket.recv(msg, flags | DONTWAIT))
+            .await
+    }
+
+    /// Receive a message into a fresh `Message`.
+    pub async fn recv_msg(&self, flags: i32) -> Result<Message> {
+        let mut msg = Message::new();
+        self.recv(&mut msg, flags).await?;
+        Ok(msg)
+    }
+
+    /// Receive a message as a byte vector.
+    pub async fn recv_bytes(&self, flags: i32) -> Result<Vec<u8>> {
+        self.retry(POLLIN, |socket| socket.recv_bytes(flags | DONTWAIT))
+            .await
+    }
+
+    /// Receive a `String`, see `Socket::recv_string()`.
+    pub async fn recv_string(&self, flags: i32) -> Result<result::Result<String, Vec<u8>>> {
+        self.retry(POLLIN, |socket| socket.recv_string(flags | DONTWAIT))
+            .await
+    }
+
+    /// Receive a multi-part message, see `Socket::recv_multipart()`.
+    ///
+    /// Only receiving the first part may have to wait. libzmq delivers
+    /// messages atomically, so the remaining parts are received without
+    /// yielding.
+    pub async fn recv_multipart(&self, flags: i32) -> Result<Vec<Vec<u8>>> {
+        let mut parts = vec![self.recv_bytes(flags).await?];
+        while self.socket.get_rcvmore()? {
+            parts.push(self.socket.recv_bytes(flags | DONTWAIT)?);
+        }
+        Ok(parts)
+    }
+}
+
+/// Asynchronously receives the events of a socket monitor.
+///
+/// ```no_run
+/// # async fn run(ctx: zmq::Context, socket: zmq::Socket) -> zmq::Result<()> {
+/// let monitor = zmq::AsyncMonitor::new(&ctx, &socket, zmq::SocketEvent::ALL as i32)?;
+/// loop {
+///     let event = monitor.next_event().await?;
+///     println!("{:?} on {}", event.event(), event.endpoint());
+/// }
+/// # }
+/// ```
+pub struct AsyncMonitor {
+    socket: AsyncSocket,
+}
+
+impl AsyncMonitor {
+    /// Start monitoring `socket` for `events`, a bitmask of
+    /// `SocketEvent` values, see `Socket::monitor()`.
+    pub fn new(ctx: &Context, socket: &Socket, events: i32) -> Result<AsyncMonitor> {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let endpoint = format!(
+            "inproc://zmq-async-monitor-{}",
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        socket.monitor(&endpoint, events)?;
+        let pair = ctx.socket(PAIR)?;
+        pair.connect(endpoint.as_str())?;
+        let socket = AsyncSocket::new(pair).map_err(io_error_to_zmq)?;
+        Ok(AsyncMonitor { socket })
+    }
+
+    /// Wait for the next event.
+    pub async fn next_event(&self) -> Result<MonitorEvent> {
+        let frames = self.socket.recv_multipart(0).await?;
+        MonitorEvent::from_frames(&frames)
+    }
+}
+
+/// Send a copy of `parts` to `capture`, dropping it if the capture
+/// socket is not ready to take it.
+fn send_capture(capture: &Socket, parts: &[Message]) -> Result<()> {
+    for (i, part) in parts.iter().enumerate() {
+        let flags = if i + 1 < parts.len() { SNDMORE } else { 0 };
+        match capture.send(&part[..], flags | DONTWAIT) {
+            // Once the first part is accepted, so are the others.
+            Err(Error::EAGAIN) if i == 0 => return Ok(()),
+            result => result?,
+        }
+    }
+    Ok(())
+}
+
+/// The maximum number of messages forwarded by a single poll of
+/// `ProxyAsync`, before yielding to other tasks.
+const PROXY_BUDGET: usize = 64;
+
+/// Forward messages between `frontend` and `backend` in both
+/// directions, like `zmq::proxy()`, but without blocking a thread.
+///
+/// A message is only taken from one socket once the other one is ready
+/// to send it, so a slow peer exerts backpressure on the sender instead
+/// of messages piling up in the proxy. Use `ProxyAsync::capture()` to
+/// also send a copy of every message to a capture socket.
+///
+/// The returned future only completes on error, e.g. with `ETERM` once
+/// the context is terminated. Drop it to stop the proxy. Messages are
+/// always sent as a whole, so neither socket is left in the middle of a
+/// message; a message that has been received but not sent yet is
+/// discarded.
+///
+/// ```no_run
+/// # async fn run(frontend: zmq::AsyncSocket, backend: zmq::AsyncSocket,
+/// #              capture: zmq::AsyncSocket) -> zmq::Result<()> {
+/// zmq::proxy_async(&frontend, &backend).capture(&capture).await
+/// # }
+/// ```
+pub fn proxy_async<'a>(frontend: &'a AsyncSocket, backend: &'a AsyncSocket) -> ProxyAsync<'a> {
+    ProxyAsync {
+        sockets: [frontend, backend],
+        capture: None,
+        pending: [None, None],
+    }
+}
+
+/// The future returned by `proxy_async()`.
+#[must_use = "futures do nothing unless polled"]
+pub struct ProxyAsync<'a> {
+    sockets: [&'a AsyncSocket; 2],
+    capture: Option<&'a AsyncSocket>,
+    // A message waiting for the other socket to accept it, indexed by
+    // the socket it was received from.
+    pending: [Option<Vec<Message>>; 2],
+}
+
+impl<'a> ProxyAsync<'a> {
+    /// Send a copy of every forwarded message to `capture`.
+    ///
+    /// Messages are dropped from the capture stream, rather than
+    /// stalling the proxy, while `capture` is not ready to send.
+    pub fn capture(mut self, capture: &'a AsyncSocket) -> ProxyAsync<'a> {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Make progress forwarding messages from `sockets[from]` to the
+    /// other socket; return whether a message was forwarded.
+    fn poll_forward(&mut self, cx: &mut TaskContext<'_>, from: usize) -> Result<bool> {
+        let (source, sink) = (self.sockets[from], self.sockets[1 - from]);
+        if self.pending[from].is_none() {
+            if sink.poll_events(cx, POLLOUT)?.is_pending()
+                || source.poll_events(cx, POLLIN)?.is_pending()
+            {
+                return Ok(false);
+            }
+            let parts = match recv_parts(&source.socket, DONTWAIT) {
+                Err(Error::EAGAIN) => return Ok(false),
+                result => result?,
+            };
+            if let Some(capture) = self.capture {
+                send_capture(&capture.socket, &parts)?;
+            }
+            self.pending[from] = Some(parts);
+        }
+        let parts = self.pending[from].as_mut().unwrap();
+        let more = if parts.len() > 1 { SNDMORE } else { 0 };
+        match sink.socket.send_in_place(&mut parts[0], more | DONTWAIT) {
+            Ok(()) => {}
+            Err(Error::EAGAIN) => {
+                if sink.poll_events(cx, POLLOUT)?.is_ready() {
+                    // The socket claims to be writable, but isn't; try
+                    // again later rather than spinning.
+                    cx.waker().wake_by_ref();
+                }
+                return Ok(false);
+            }
+            Err(e) => return Err(e),
+        }
+        // Once the first part is accepted, so are the others, so they
+        // are sent right away and the sink is never left in the middle
+        // of a message.
+        let parts = self.pending[from].take().unwrap();
+        sink.socket
+            .send_multipart(parts.into_iter().skip(1), DONTWAIT)?;
+        Ok(true)
+    }
+}
+
+impl Future for ProxyAsync<'_> {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        for _ in 0..PROXY_BUDGET {
+            let mut forwarded = false;
+            for from in 0..2 {
+                match this.poll_forward(cx, from) {
+                    Ok(done) => forwarded |= done,
+                    Err(e) => return Poll::Ready(Err(e)),
+                }
+            }
+            if !forwarded {
+                return Poll::Pending;
+            }
+        }
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
@@ -0,0 +1,216 @@
+//! Peer discovery on a LAN by UDP broadcast.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::io::AsFd;
+#[cfg(windows)]
+use std::os::windows::io::AsSocket;
+use std::str;
+use std::time::{Duration, Instant};
+
+use log::warn;
+use socket2::{Domain, Protocol, Type};
+
+use crate::{
+    io_error_to_zmq, poll, Actor, Context, Error, PollItem, Result, Socket, DONTWAIT, POLLIN,
+    SNDMORE,
+};
+
+/// The largest payload a beacon can broadcast.
+pub const BEACON_MAX: usize = 255;
+
+/// A beacon for discovering peers on a LAN, like CZMQ's `zbeacon`.
+///
+/// Each beacon binds a UDP socket to the given port, and runs an actor
+/// that broadcasts a payload at a regular interval (see `publish()`),
+/// and reports beacons received from peers whose payload starts with a
+/// given prefix (see `subscribe()`). Received beacons, including the
+/// beacon's own broadcasts, are delivered as two frames, the IP address
+/// of the sender and the payload, on the socket returned by
+/// `get_ref()`, which can be used with `zmq::poll()`; `recv()` decodes
+/// them.
+///
+/// ```no_run
+/// # fn main() -> zmq::Result<()> {
+/// use std::time::Duration;
+///
+/// let ctx = zmq::Context::new();
+/// let beacon = zmq::Beacon::new(&ctx, 9999)?;
+/// beacon.publish(b"SVC tcp://192.168.1.10:5555", Duration::from_secs(1))?;
+/// beacon.subscribe(b"SVC ")?;
+/// let (peer, payload) = beacon.recv(0)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Beacon {
+    actor: Actor,
+}
+
+impl Beacon {
+    /// Start a beacon on UDP `port`, broadcasting to all hosts on the
+    /// local network (`255.255.255.255`).
+    pub fn new(ctx: &Context, port: u16) -> Result<Beacon> {
+        Beacon::with_broadcast_address(ctx, port, Ipv4Addr::BROADCAST)
+    }
+
+    /// Start a beacon on UDP `port`, broadcasting to `address`, e.g. the
+    /// broadcast address of a specific network, or `127.255.255.255` to
+    /// stay on the loopback interface.
+    pub fn with_broadcast_address(ctx: &Context, port: u16, address: Ipv4Addr) -> Result<Beacon> {
+        let udp = bind_udp(port).map_err(io_error_to_zmq)?;
+        let target = SocketAddr::V4(SocketAddrV4::new(address, port));
+        let actor = Actor::spawn(ctx, move |pipe| run(pipe, udp, target))?;
+        Ok(Beacon { actor })
+    }
+
+    /// Start broadcasting `payload` every `interval`, replacing any
+    /// payload published before.
+    ///
+    /// Fails with `EINVAL` if the payload is larger than `BEACON_MAX`,
+    /// or the interval is shorter than a millisecond.
+    pub fn publish(&self, payload: &[u8], interval: Duration) -> Result<()> {
+        if payload.len() > BEACON_MAX || interval < Duration::from_millis(1) {
+            return Err(Error::EINVAL);
+        }
+        let interval = interval.as_millis().to_string();
+        self.actor
+            .send_multipart([&b"PUBLISH"[..], payload, interval.as_bytes()], 0)
+    }
+
+    /// Stop broadcasting.
+    pub fn silence(&self) -> Result<()> {
+        self.actor.send("SILENCE", 0)
+    }
+
+    /// Report beacons whose payload starts with `prefix`, replacing any
+    /// previous subscription. An empty prefix matches all beacons.
+    pub fn subscribe(&self, prefix: &[u8]) -> Result<()> {
+        self.actor.send_multipart([&b"SUBSCRIBE"[..], prefix], 0)
+    }
+
+    /// Stop reporting beacons.
+    pub fn unsubscribe(&self) -> Result<()> {
+        self.actor.send("UNSUBSCRIBE", 0)
+    }
+
+    /// Get a reference to the socket received beacons are delivered on,
+    /// e.g. for polling it.
+    pub fn get_ref(&self) -> &Socket {
+        &self.actor
+    }
+
+    /// Receive the address of a peer and the payload of its beacon.
+    pub fn recv(&self, flags: i32) -> Result<(IpAddr, Vec<u8>)> {
+        let mut parts = self.actor.recv_multipart(flags)?;
+        if parts.len() != 2 {
+            return Err(Error::EPROTO);
+        }
+        let payload = parts.pop().unwrap();
+        let peer = str::from_utf8(&parts[0])
+            .ok()
+            .and_then(|peer| peer.parse().ok())
+            .ok_or(Error::EPROTO)?;
+        Ok((peer, payload))
+    }
+
+    /// Stop the beacon, and wait for its actor to finish.
+    pub fn terminate(self) -> Result<()> {
+        self.actor.terminate()
+    }
+}
+
+/// Bind a UDP socket for sending and receiving broadcasts on `port`,
+/// shared with other beacons on the same host.
+fn bind_udp(port: u16) -> std::io::Result<UdpSocket> {
+    let socket = socket2::Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+    socket.set_reuse_port(true)?;
+    socket.set_broadcast(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)).into())?;
+    Ok(socket.into())
+}
+
+#[cfg(unix)]
+fn udp_poll_item(udp: &UdpSocket) -> PollItem<'_> {
+    PollItem::from_borrowed_fd(udp.as_fd(), POLLIN)
+}
+
+#[cfg(windows)]
+fn udp_poll_item(udp: &UdpSocket) -> PollItem<'_> {
+    PollItem::from_borrowed_socket(udp.as_socket(), POLLIN)
+}
+
+/// The beacon's actor.
+fn run(pipe: Socket, udp: UdpSocket, target: SocketAddr) -> Result<()> {
+    let mut publication: Option<(Vec<u8>, Duration)> = None;
+    let mut next_ping = Instant::now();
+    let mut filter: Option<Vec<u8>> = None;
+    let mut buf = [0; BEACON_MAX + 1];
+    loop {
+        let timeout = match publication {
+            Some(_) => next_ping
+                .saturating_duration_since(Instant::now())
+                .as_millis() as i64,
+            None => -1,
+        };
+        let (command, beacon) = {
+            let mut items = [pipe.as_poll_item(POLLIN), udp_poll_item(&udp)];
+            poll(&mut items, timeout)?;
+            (items[0].is_readable(), items[1].is_readable())
+        };
+        if command {
+            let mut parts = pipe.recv_multipart(0)?.into_iter();
+            let name = parts.next().unwrap_or_default();
+            match &name[..] {
+                b"$TERM" => return Ok(()),
+                b"PUBLISH" => {
+                    let payload = parts.next().unwrap_or_default();
+                    let interval = parts
+                        .next()
+                        .and_then(|millis| str::from_utf8(&millis).ok()?.parse().ok())
+                        .ok_or(Error::EPROTO)?;
+                    publication = Some((payload, Duration::from_millis(interval)));
+                    next_ping = Instant::now();
+                }
+                b"SILENCE" => publication = None,
+                b"SUBSCRIBE" => filter = Some(parts.next().unwrap_or_default()),
+                b"UNSUBSCRIBE" => filter = None,
+                _ => return Err(Error::EPROTO),
+            }
+        }
+        if beacon {
+            match udp.recv_from(&mut buf) {
+                Ok((len, peer)) => {
+                    let payload = &buf[..len];
+                    if len <= BEACON_MAX && filter.as_ref().is_some_and(|f| payload.starts_with(f))
+                    {
+                        report(&pipe, peer.ip(), payload)?;
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(e) => warn!("beacon failed to receive: {}", e),
+            }
+        }
+        if let Some((payload, interval)) = &publication {
+            let now = Instant::now();
+            if now >= next_ping {
+                if let Err(e) = udp.send_to(payload, target) {
+                    warn!("beacon failed to broadcast to {}: {}", target, e);
+                }
+                next_ping = now + *interval;
+            }
+        }
+    }
+}
+
+/// Pass a received beacon to the parent, dropping it if the parent is
+/// not keeping up, so that commands are still handled.
+fn report(pipe: &Socket, peer: IpAddr, payload: &[u8]) -> Result<()> {
+    match pipe.send(peer.to_string().as_bytes(), SNDMORE | DONTWAIT) {
+        Ok(()) => pipe.send(payload, 0),
+        Err(Error::EAGAIN) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,273 @@
+//! Active/passive failover with the Binary Star pattern.
+//!
+//! A Binary Star pair is a primary and a backup server. Each publishes
+//! its state to the other over `PUB`/`SUB` at every heartbeat, and only
+//! the active one serves clients. When the active server goes away,
+//! clients fail over to the passive one, which takes over once it has
+//! missed two heartbeats and receives a request. It never takes over on
+//! its own: if the servers merely lost sight of each other, clients
+//! that can still reach the active server keep it active.
+//!
+//! Clients send their requests to the voter socket, which the
+//! application registers with `BinaryStar::voter()`. Requests that the
+//! state machine rejects, e.g. because the server is passive, are
+//! dropped, so clients must retry with the other server after a
+//! timeout, like `pirate::Client` does with a single server.
+//!
+//! ```no_run
+//! # fn main() -> zmq::Result<()> {
+//! use zmq::bstar::BinaryStar;
+//!
+//! let ctx = zmq::Context::new();
+//! let mut bstar = BinaryStar::new(&ctx, true, "tcp://*:5003", "tcp://localhost:5004")?;
+//! bstar.voter("tcp://*:5001", zmq::ROUTER, |socket| {
+//!     let request = socket.recv_multipart(0)?;
+//!     socket.send_multipart(request, 0)
+//! })?;
+//! bstar.on_active(|| println!("serving clients"));
+//! bstar.on_passive(|| println!("standing by"));
+//! bstar.run()
+//! # }
+//! ```
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use log::{error, warn};
+
+use crate::reliable::millis;
+use crate::{poll, Context, Error, Result, Socket, SocketType, POLLIN, PUB, SUB};
+
+/// The default interval between state messages.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// The state of a Binary Star server.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    /// A primary server, waiting for its peer.
+    Primary,
+    /// A backup server, waiting for its peer.
+    Backup,
+    /// Serving clients.
+    Active,
+    /// Standing by while the peer serves clients.
+    Passive,
+}
+
+impl State {
+    fn to_bytes(self) -> &'static [u8] {
+        match self {
+            State::Primary => b"1",
+            State::Backup => b"2",
+            State::Active => b"3",
+            State::Passive => b"4",
+        }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<State> {
+        match bytes {
+            b"1" => Some(State::Primary),
+            b"2" => Some(State::Backup),
+            b"3" => Some(State::Active),
+            b"4" => Some(State::Passive),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            State::Primary => "primary",
+            State::Backup => "backup",
+            State::Active => "active",
+            State::Passive => "passive",
+        };
+        f.write_str(name)
+    }
+}
+
+/// An input to the state machine.
+#[derive(Clone, Copy, Debug)]
+enum Event {
+    /// The peer reported its state.
+    Peer(State),
+    /// A client sent a request to the voter socket.
+    ClientRequest,
+}
+
+type Handler<'a> = Box<dyn FnMut(&Socket) -> Result<()> + 'a>;
+type Callback<'a> = Box<dyn FnMut() + 'a>;
+
+/// One server of a Binary Star pair.
+pub struct BinaryStar<'a> {
+    ctx: Context,
+    state: State,
+    statepub: Socket,
+    statesub: Socket,
+    voter: Option<(Socket, Handler<'a>)>,
+    on_active: Option<Callback<'a>>,
+    on_passive: Option<Callback<'a>>,
+    heartbeat_interval: Duration,
+    peer_expiry: Instant,
+}
+
+impl<'a> BinaryStar<'a> {
+    /// Create the primary (if `primary` is true) or backup server of a
+    /// pair, publishing its state on `local` and subscribing to its
+    /// peer's state at `remote`.
+    pub fn new(ctx: &Context, primary: bool, local: &str, remote: &str) -> Result<BinaryStar<'a>> {
+        let statepub = ctx.socket(PUB)?;
+        statepub.bind(local)?;
+        let statesub = ctx.socket(SUB)?;
+        statesub.set_subscribe(b"")?;
+        statesub.connect(remote)?;
+        Ok(BinaryStar {
+            ctx: ctx.clone(),
+            state: if primary {
+                State::Primary
+            } else {
+                State::Backup
+            },
+            statepub,
+            statesub,
+            voter: None,
+            on_active: None,
+            on_passive: None,
+            heartbeat_interval: HEARTBEAT_INTERVAL,
+            peer_expiry: Instant::now() + HEARTBEAT_INTERVAL * 2,
+        })
+    }
+
+    /// Bind a socket of type `socket_type` to `endpoint` for receiving
+    /// client requests.
+    ///
+    /// When the socket becomes readable and the server may serve
+    /// clients, `handler` is called to receive and answer the request.
+    /// Otherwise, the request is dropped. An error returned by `handler`
+    /// stops `run()`.
+    pub fn voter<F>(&mut self, endpoint: &str, socket_type: SocketType, handler: F) -> Result<()>
+    where
+        F: FnMut(&Socket) -> Result<()> + 'a,
+    {
+        let socket = self.ctx.socket(socket_type)?;
+        socket.bind(endpoint)?;
+        self.voter = Some((socket, Box::new(handler)));
+        Ok(())
+    }
+
+    /// Call `callback` whenever the server becomes active.
+    pub fn on_active<F: FnMut() + 'a>(&mut self, callback: F) {
+        self.on_active = Some(Box::new(callback));
+    }
+
+    /// Call `callback` whenever the server becomes passive.
+    pub fn on_passive<F: FnMut() + 'a>(&mut self, callback: F) {
+        self.on_passive = Some(Box::new(callback));
+    }
+
+    /// Set the interval between state messages, which must match the
+    /// peer's setting. Defaults to one second.
+    ///
+    /// A peer that has been silent for two intervals is considered dead.
+    pub fn set_heartbeat_interval(&mut self, interval: Duration) {
+        self.heartbeat_interval = interval;
+        self.peer_expiry = Instant::now() + interval * 2;
+    }
+
+    /// Return the current state of the server.
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Run the server in the current thread.
+    ///
+    /// This only returns (always with an `Err`) when an operation on one
+    /// of the sockets or the voter handler fails, typically with `ETERM`
+    /// because the context has been shut down, or with `EPROTO` when
+    /// both servers are active or both are passive, which means the pair
+    /// is misconfigured.
+    pub fn run(&mut self) -> Result<()> {
+        let mut heartbeat_at = Instant::now();
+        loop {
+            if Instant::now() >= heartbeat_at {
+                self.statepub.send(self.state.to_bytes(), 0)?;
+                heartbeat_at = Instant::now() + self.heartbeat_interval;
+            }
+            let timeout = heartbeat_at.saturating_duration_since(Instant::now());
+            let (peer_ready, voter_ready) = {
+                let mut items = vec![self.statesub.as_poll_item(POLLIN)];
+                if let Some((voter, _)) = &self.voter {
+                    items.push(voter.as_poll_item(POLLIN));
+                }
+                poll(&mut items, millis(timeout))?;
+                (
+                    items[0].is_readable(),
+                    items.get(1).is_some_and(|item| item.is_readable()),
+                )
+            };
+            if peer_ready {
+                let message = self.statesub.recv_bytes(0)?;
+                match State::from_bytes(&message) {
+                    Some(peer) => {
+                        self.peer_expiry = Instant::now() + self.heartbeat_interval * 2;
+                        if !self.execute(Event::Peer(peer)) {
+                            error!("bstar: fatal error, peer is {} as well", peer);
+                            return Err(Error::EPROTO);
+                        }
+                    }
+                    None => warn!("bstar: ignoring invalid state message"),
+                }
+            }
+            if voter_ready {
+                let accepted = self.execute(Event::ClientRequest);
+                let (voter, handler) = self.voter.as_mut().unwrap();
+                if accepted {
+                    handler(voter)?;
+                } else {
+                    voter.recv_multipart(0)?;
+                }
+            }
+        }
+    }
+
+    /// Apply `event` to the state machine, returning false if it is
+    /// rejected.
+    fn execute(&mut self, event: Event) -> bool {
+        let peer_expired = Instant::now() >= self.peer_expiry;
+        let next = match (self.state, event) {
+            // The peer's state tells the primary or backup which role to
+            // take on, with the primary preferring to be active.
+            (State::Primary, Event::Peer(State::Backup)) => State::Active,
+            (State::Primary, Event::Peer(State::Active)) => State::Passive,
+            (State::Backup, Event::Peer(State::Active)) => State::Passive,
+            // A restarted peer joins as the passive server.
+            (State::Passive, Event::Peer(State::Primary)) => State::Active,
+            (State::Passive, Event::Peer(State::Backup)) => State::Active,
+            // Clients only make the server active if the peer is gone;
+            // the backup never serves clients before seeing its peer.
+            (State::Primary, Event::ClientRequest) | (State::Passive, Event::ClientRequest) => {
+                if !peer_expired {
+                    return false;
+                }
+                State::Active
+            }
+            (State::Backup, Event::ClientRequest) => return false,
+            (State::Active, Event::Peer(State::Active)) => return false,
+            (State::Passive, Event::Peer(State::Passive)) => return false,
+            (state, _) => state,
+        };
+        if next != self.state {
+            self.state = next;
+            let callback = match next {
+                State::Active => &mut self.on_active,
+                State::Passive => &mut self.on_passive,
+                _ => return true,
+            };
+            if let Some(callback) = callback {
+                callback();
+            }
+        }
+        true
+    }
+}
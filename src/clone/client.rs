@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use log::warn;
+
+use super::{KvMessage, HUGZ, ICANHAZ, KTHXBAI};
+use crate::reliable::millis;
+use crate::{Context, Error, Result, Socket, DEALER, POLLIN, PUSH, SUB};
+
+/// The default time to wait for a snapshot or an update.
+const TIMEOUT: Duration = Duration::from_millis(2500);
+
+/// A clone client, holding a replica of the server's key-value state,
+/// or of a subtree of it.
+///
+/// The replica only changes in `recv()`, when an update from the server
+/// is applied, including the client's own updates.
+pub struct Client {
+    ctx: Context,
+    server: String,
+    snapshot: Socket,
+    subscriber: Socket,
+    publisher: Socket,
+    subtree: String,
+    kvmap: BTreeMap<String, (KvMessage, Option<Instant>)>,
+    sequence: u64,
+    timeout: Duration,
+}
+
+impl Client {
+    /// Connect to the server's snapshot, publisher and collector
+    /// endpoints, and fetch a snapshot of the keys starting with
+    /// `subtree`, which may be empty.
+    ///
+    /// Fails with `ETIMEDOUT` if no heartbeat or snapshot arrives within
+    /// the timeout.
+    pub fn new(
+        ctx: &Context,
+        snapshot: &str,
+        publisher: &str,
+        collector: &str,
+        subtree: &str,
+    ) -> Result<Client> {
+        let server = snapshot.to_owned();
+        let subscriber = ctx.socket(SUB)?;
+        subscriber.set_subscribe(subtree.as_bytes())?;
+        subscriber.set_subscribe(HUGZ.as_bytes())?;
+        subscriber.connect(publisher)?;
+        let push = ctx.socket(PUSH)?;
+        push.connect(collector)?;
+        let mut client = Client {
+            ctx: ctx.clone(),
+            snapshot: connect(ctx, &server)?,
+            server,
+            subscriber,
+            publisher: push,
+            subtree: subtree.to_owned(),
+            kvmap: BTreeMap::new(),
+            sequence: 0,
+            timeout: TIMEOUT,
+        };
+        // Only fetch the snapshot once the subscription is in place, so
+        // that no updates are missed in between.
+        client.wait_for_heartbeat()?;
+        client.fetch_snapshot()?;
+        Ok(client)
+    }
+
+    /// Set the time to wait for a snapshot or an update. Defaults to 2.5
+    /// seconds.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Get a reference to the socket receiving updates, e.g. for polling
+    /// it.
+    pub fn get_ref(&self) -> &Socket {
+        &self.subscriber
+    }
+
+    /// Return the sequence number of the latest update applied.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Get the value of `key`, unless it is unknown or has expired.
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        let (update, expiry) = self.kvmap.get(key)?;
+        match expiry {
+            Some(expiry) if *expiry <= Instant::now() => None,
+            _ => Some(update.body()),
+        }
+    }
+
+    /// Send an update setting `key` to `value` to the server, or deleting
+    /// it if `value` is empty. The server deletes the key after `ttl`,
+    /// if given.
+    pub fn set<K, V>(&self, key: K, value: V, ttl: Option<Duration>) -> Result<()>
+    where
+        K: Into<String>,
+        V: Into<Vec<u8>>,
+    {
+        let mut update = KvMessage::new(key, value);
+        if let Some(ttl) = ttl {
+            update.set_ttl(ttl);
+        }
+        update.send(&self.publisher, 0)
+    }
+
+    /// Wait for the next update from the server, apply it, and return
+    /// it.
+    ///
+    /// When a client of the whole tree detects that it has missed
+    /// updates, it fetches a new snapshot before carrying on; clients of
+    /// a subtree cannot, since its updates are not numbered
+    /// consecutively. Fails with `ETIMEDOUT` if the server has been
+    /// silent for the timeout, which means it is gone.
+    pub fn recv(&mut self) -> Result<KvMessage> {
+        loop {
+            if self.subscriber.poll(POLLIN, millis(self.timeout))? == 0 {
+                return Err(Error::ETIMEDOUT);
+            }
+            let update = match KvMessage::recv(&self.subscriber, 0) {
+                Ok(update) => update,
+                Err(Error::EPROTO) => {
+                    warn!("clone client: ignoring invalid update");
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let expected = self.sequence + 1;
+            if update.key() == HUGZ {
+                if self.subtree.is_empty() && update.sequence() >= expected {
+                    warn!("clone client: missed updates, fetching a snapshot");
+                    self.fetch_snapshot()?;
+                }
+            } else if update.sequence() < expected {
+                // Already part of the snapshot.
+            } else if self.subtree.is_empty() && update.sequence() > expected {
+                warn!("clone client: missed updates, fetching a snapshot");
+                self.fetch_snapshot()?;
+            } else {
+                self.sequence = update.sequence();
+                self.apply(update.clone());
+                return Ok(update);
+            }
+        }
+    }
+
+    /// Wait for a heartbeat from the server, discarding any updates.
+    fn wait_for_heartbeat(&self) -> Result<()> {
+        loop {
+            if self.subscriber.poll(POLLIN, millis(self.timeout))? == 0 {
+                return Err(Error::ETIMEDOUT);
+            }
+            match KvMessage::recv(&self.subscriber, 0) {
+                Ok(update) if update.key() == HUGZ => return Ok(()),
+                Ok(_) | Err(Error::EPROTO) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Replace the replica with a snapshot from the server.
+    fn fetch_snapshot(&mut self) -> Result<()> {
+        let request = [ICANHAZ.as_bytes(), self.subtree.as_bytes()];
+        self.snapshot.send_multipart(request, 0)?;
+        self.kvmap.clear();
+        loop {
+            if self.snapshot.poll(POLLIN, millis(self.timeout))? == 0 {
+                // A late snapshot must not be mistaken for the next one.
+                self.snapshot = connect(&self.ctx, &self.server)?;
+                return Err(Error::ETIMEDOUT);
+            }
+            let update = KvMessage::recv(&self.snapshot, 0)?;
+            if update.key() == KTHXBAI {
+                self.sequence = update.sequence();
+                return Ok(());
+            }
+            self.apply(update);
+        }
+    }
+
+    fn apply(&mut self, update: KvMessage) {
+        if update.is_delete() {
+            self.kvmap.remove(update.key());
+        } else {
+            let expiry = update.expiry();
+            self.kvmap.insert(update.key().to_owned(), (update, expiry));
+        }
+    }
+}
+
+fn connect(ctx: &Context, server: &str) -> Result<Socket> {
+    let socket = ctx.socket(DEALER)?;
+    socket.set_linger(0)?;
+    socket.connect(server)?;
+    Ok(socket)
+}
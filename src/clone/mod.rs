@@ -0,0 +1,227 @@
+//! Reliable replication of key-value state with the Clone pattern.
+//!
+//! A `Server` holds the authoritative state, a map of string keys to
+//! binary values. Clients send updates to the server over `PUSH`/`PULL`;
+//! the server gives each one the next sequence number, and publishes it
+//! to all clients over `PUB`/`SUB`. A `Client` joining later waits for
+//! the server's first heartbeat, which shows that its subscription is in
+//! place, asks for a snapshot of the state over `DEALER`/`ROUTER`, and
+//! then applies the published updates that are newer than the snapshot.
+//! When it detects a gap in the sequence, it fetches a new snapshot.
+//!
+//! Clients may limit themselves to a subtree, i.e. the keys starting
+//! with a prefix. An update with an empty value deletes its key, and an
+//! update with a `ttl` property is deleted by the server after that many
+//! seconds. While there are no updates, the server publishes a heartbeat
+//! with the key `HUGZ` and its current sequence number.
+//!
+//! Each `KvMessage` is sent as five frames: the key, the sequence number
+//! as 8 bytes in network order, a UUID, the properties as `name=value`
+//! lines, and the value. Snapshot requests are an `ICANHAZ?` frame and
+//! the subtree; a snapshot ends with a `KTHXBAI` message, whose
+//! sequence number is that of the snapshot and whose value is the
+//! subtree.
+//!
+//! ```
+//! # fn main() -> zmq::Result<()> {
+//! use zmq::clone::{Client, Server};
+//!
+//! let ctx = zmq::Context::new();
+//! let mut server = Server::new(&ctx)?;
+//! server.bind_snapshot("inproc://clone-doc-snapshot")?;
+//! server.bind_publisher("inproc://clone-doc-publisher")?;
+//! server.bind_collector("inproc://clone-doc-collector")?;
+//! std::thread::spawn(move || server.run());
+//!
+//! let mut client = Client::new(
+//!     &ctx,
+//!     "inproc://clone-doc-snapshot",
+//!     "inproc://clone-doc-publisher",
+//!     "inproc://clone-doc-collector",
+//!     "",
+//! )?;
+//! client.set("greeting", "hello", None)?;
+//! let update = client.recv()?;
+//! assert_eq!(update.key(), "greeting");
+//! assert_eq!(client.get("greeting"), Some(&b"hello"[..]));
+//! # Ok(())
+//! # }
+//! ```
+
+mod client;
+mod server;
+
+pub use self::client::Client;
+pub use self::server::Server;
+
+use std::convert::TryInto;
+use std::time::{Duration, Instant};
+
+use crate::{Error, Message, Result, Socket};
+
+/// The first frame of a snapshot request.
+const ICANHAZ: &str = "ICANHAZ?";
+/// The key of the message ending a snapshot.
+const KTHXBAI: &str = "KTHXBAI";
+/// The key of heartbeat messages.
+const HUGZ: &str = "HUGZ";
+/// The property holding the time to live, in seconds.
+const TTL: &str = "ttl";
+
+/// The default interval between heartbeats.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// An update to a key, as exchanged by clone servers and clients.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KvMessage {
+    key: String,
+    sequence: u64,
+    uuid: Vec<u8>,
+    properties: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl KvMessage {
+    /// Create an update setting `key` to `body`, or deleting it if
+    /// `body` is empty.
+    pub fn new<K, B>(key: K, body: B) -> KvMessage
+    where
+        K: Into<String>,
+        B: Into<Vec<u8>>,
+    {
+        KvMessage {
+            key: key.into(),
+            body: body.into(),
+            ..KvMessage::default()
+        }
+    }
+
+    /// The key being updated.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The sequence number, assigned by the server.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Set the sequence number. Clients send updates with 0.
+    pub fn set_sequence(&mut self, sequence: u64) {
+        self.sequence = sequence;
+    }
+
+    /// The UUID identifying the update, empty unless set by the sender.
+    pub fn uuid(&self) -> &[u8] {
+        &self.uuid
+    }
+
+    /// Set the UUID identifying the update.
+    pub fn set_uuid<U: Into<Vec<u8>>>(&mut self, uuid: U) {
+        self.uuid = uuid.into();
+    }
+
+    /// The new value, which is empty if the key is deleted.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Return the new value.
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+
+    /// Return true if the update deletes the key.
+    pub fn is_delete(&self) -> bool {
+        self.body.is_empty()
+    }
+
+    /// Get the value of the property `name`.
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(property, _)| property == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Set the property `name`, which must not contain `=` or newlines,
+    /// nor must `value` contain newlines.
+    pub fn set_property<V: Into<String>>(&mut self, name: &str, value: V) {
+        let value = value.into();
+        match self
+            .properties
+            .iter_mut()
+            .find(|(property, _)| property == name)
+        {
+            Some((_, old)) => *old = value,
+            None => self.properties.push((name.to_owned(), value)),
+        }
+    }
+
+    /// The time after which the server deletes the key, if any.
+    pub fn ttl(&self) -> Option<Duration> {
+        let seconds: f64 = self.property(TTL)?.parse().ok()?;
+        Duration::try_from_secs_f64(seconds).ok()
+    }
+
+    /// Have the server delete the key `ttl` after the update.
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.set_property(TTL, ttl.as_secs_f64().to_string());
+    }
+
+    /// Send the message on `socket`, as five frames. With `SNDMORE` in
+    /// `flags`, more frames may follow.
+    pub fn send(&self, socket: &Socket, flags: i32) -> Result<()> {
+        socket.send_multipart(self.to_frames(), flags)
+    }
+
+    /// Receive a message from `socket`.
+    ///
+    /// Fails with `EPROTO` if the message is not a valid `KvMessage`.
+    pub fn recv(socket: &Socket, flags: i32) -> Result<KvMessage> {
+        KvMessage::from_frames(socket.recv_multipart(flags)?)
+    }
+
+    fn to_frames(&self) -> [Message; 5] {
+        let properties: String = self
+            .properties
+            .iter()
+            .map(|(name, value)| format!("{}={}\n", name, value))
+            .collect();
+        [
+            Message::from(self.key.as_str()),
+            Message::from(&self.sequence.to_be_bytes()[..]),
+            Message::from(&self.uuid[..]),
+            Message::from(properties.as_str()),
+            Message::from(&self.body[..]),
+        ]
+    }
+
+    fn from_frames(frames: Vec<Vec<u8>>) -> Result<KvMessage> {
+        let [key, sequence, uuid, properties, body]: [Vec<u8>; 5] =
+            frames.try_into().map_err(|_| Error::EPROTO)?;
+        let key = String::from_utf8(key).map_err(|_| Error::EPROTO)?;
+        let sequence = u64::from_be_bytes(sequence[..].try_into().map_err(|_| Error::EPROTO)?);
+        let properties = String::from_utf8(properties).map_err(|_| Error::EPROTO)?;
+        let properties = properties
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let (name, value) = line.split_once('=').ok_or(Error::EPROTO)?;
+                Ok((name.to_owned(), value.to_owned()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(KvMessage {
+            key,
+            sequence,
+            uuid,
+            properties,
+            body,
+        })
+    }
+
+    /// The time at which the key expires, if the update was received now.
+    fn expiry(&self) -> Option<Instant> {
+        self.ttl().map(|ttl| Instant::now() + ttl)
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use log::warn;
+
+use super::{KvMessage, HEARTBEAT_INTERVAL, HUGZ, ICANHAZ, KTHXBAI};
+use crate::reliable::millis;
+use crate::{poll, Context, Error, Result, Socket, POLLIN, PUB, PULL, ROUTER, SNDMORE};
+
+/// A clone server, holding the authoritative key-value state.
+///
+/// The server keeps no history: clients that miss updates catch up by
+/// fetching a snapshot.
+pub struct Server {
+    snapshot: Socket,
+    publisher: Socket,
+    collector: Socket,
+    /// The current updates of all keys, with their expiry times.
+    kvmap: BTreeMap<String, (KvMessage, Option<Instant>)>,
+    sequence: u64,
+    heartbeat_interval: Duration,
+}
+
+impl Server {
+    /// Create a server with new `ROUTER`, `PUB` and `PULL` sockets.
+    pub fn new(ctx: &Context) -> Result<Server> {
+        Ok(Server {
+            snapshot: ctx.socket(ROUTER)?,
+            publisher: ctx.socket(PUB)?,
+            collector: ctx.socket(PULL)?,
+            kvmap: BTreeMap::new(),
+            sequence: 0,
+            heartbeat_interval: HEARTBEAT_INTERVAL,
+        })
+    }
+
+    /// Serve snapshot requests on `endpoint`, see `Socket::bind()`.
+    pub fn bind_snapshot(&self, endpoint: &str) -> Result<()> {
+        self.snapshot.bind(endpoint)
+    }
+
+    /// Publish updates on `endpoint`, see `Socket::bind()`.
+    pub fn bind_publisher(&self, endpoint: &str) -> Result<()> {
+        self.publisher.bind(endpoint)
+    }
+
+    /// Collect updates from clients on `endpoint`, see `Socket::bind()`.
+    pub fn bind_collector(&self, endpoint: &str) -> Result<()> {
+        self.collector.bind(endpoint)
+    }
+
+    /// Set the interval between heartbeats, which is also how often
+    /// expired keys are deleted. Defaults to one second.
+    pub fn set_heartbeat_interval(&mut self, interval: Duration) {
+        self.heartbeat_interval = interval;
+    }
+
+    /// Return the sequence number of the latest update.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Run the server in the current thread.
+    ///
+    /// This only returns (always with an `Err`) when an operation on one
+    /// of the sockets fails, typically with `ETERM` because the context
+    /// has been shut down.
+    pub fn run(&mut self) -> Result<()> {
+        let mut heartbeat_at = Instant::now() + self.heartbeat_interval;
+        loop {
+            let timeout = heartbeat_at.saturating_duration_since(Instant::now());
+            let (snapshot_ready, collector_ready) = {
+                let mut items = [
+                    self.snapshot.as_poll_item(POLLIN),
+                    self.collector.as_poll_item(POLLIN),
+                ];
+                poll(&mut items, millis(timeout))?;
+                (items[0].is_readable(), items[1].is_readable())
+            };
+            if snapshot_ready {
+                self.send_snapshot()?;
+            }
+            if collector_ready {
+                match KvMessage::recv(&self.collector, 0) {
+                    Ok(update) => self.publish(update)?,
+                    Err(Error::EPROTO) => warn!("clone server: ignoring invalid update"),
+                    Err(e) => return Err(e),
+                }
+            }
+            if Instant::now() >= heartbeat_at {
+                self.flush_expired()?;
+                let mut hugz = KvMessage::new(HUGZ, "");
+                hugz.set_sequence(self.sequence);
+                hugz.send(&self.publisher, 0)?;
+                heartbeat_at = Instant::now() + self.heartbeat_interval;
+            }
+        }
+    }
+
+    /// Answer a snapshot request with the updates of all keys in the
+    /// requested subtree.
+    fn send_snapshot(&self) -> Result<()> {
+        let frames = self.snapshot.recv_multipart(0)?;
+        let (identity, subtree) = match &frames[..] {
+            [identity, request, subtree] if request == ICANHAZ.as_bytes() => (identity, subtree),
+            _ => {
+                warn!("clone server: ignoring invalid snapshot request");
+                return Ok(());
+            }
+        };
+        let now = Instant::now();
+        for (key, (update, expiry)) in &self.kvmap {
+            let expired = expiry.is_some_and(|expiry| expiry <= now);
+            if !key.as_bytes().starts_with(subtree) || expired {
+                continue;
+            }
+            self.snapshot.send(&identity[..], SNDMORE)?;
+            match expiry {
+                // Clients count the time to live from when they receive
+                // the snapshot.
+                Some(expiry) => {
+                    let mut update = update.clone();
+                    update.set_ttl(*expiry - now);
+                    update.send(&self.snapshot, 0)?;
+                }
+                None => update.send(&self.snapshot, 0)?,
+            }
+        }
+        let mut kthxbai = KvMessage::new(KTHXBAI, &subtree[..]);
+        kthxbai.set_sequence(self.sequence);
+        self.snapshot.send(&identity[..], SNDMORE)?;
+        kthxbai.send(&self.snapshot, 0)
+    }
+
+    /// Give `update` the next sequence number, apply it, and publish it.
+    fn publish(&mut self, mut update: KvMessage) -> Result<()> {
+        self.sequence += 1;
+        update.set_sequence(self.sequence);
+        update.send(&self.publisher, 0)?;
+        if update.is_delete() {
+            self.kvmap.remove(update.key());
+        } else {
+            let expiry = update.expiry();
+            self.kvmap.insert(update.key().to_owned(), (update, expiry));
+        }
+        Ok(())
+    }
+
+    /// Delete expired keys, publishing the deletes.
+    fn flush_expired(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let expired: Vec<String> = self
+            .kvmap
+            .iter()
+            .filter(|(_, (_, expiry))| expiry.is_some_and(|expiry| expiry <= now))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in expired {
+            self.publish(KvMessage::new(key, ""))?;
+        }
+        Ok(())
+    }
+}
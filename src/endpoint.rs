@@ -0,0 +1,447 @@
+//! Parsing and validation of 0MQ endpoint addresses.
+
+use std::convert::TryFrom;
+use std::ffi::CString;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use crate::Error;
+
+/// The transport of an `Endpoint`, i.e. the part before `://`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Transport {
+    Tcp,
+    Ipc,
+    Inproc,
+    Udp,
+    Ws,
+    Wss,
+    Pgm,
+    Epgm,
+    Norm,
+    Tipc,
+    Vmci,
+}
+
+impl Transport {
+    fn from_scheme(scheme: &str) -> Option<Transport> {
+        let transport = match scheme {
+            "tcp" => Transport::Tcp,
+            "ipc" => Transport::Ipc,
+            "inproc" => Transport::Inproc,
+            "udp" => Transport::Udp,
+            "ws" => Transport::Ws,
+            "wss" => Transport::Wss,
+            "pgm" => Transport::Pgm,
+            "epgm" => Transport::Epgm,
+            "norm" => Transport::Norm,
+            "tipc" => Transport::Tipc,
+            "vmci" => Transport::Vmci,
+            _ => return None,
+        };
+        Some(transport)
+    }
+
+    /// The scheme used for this transport in endpoint strings, e.g.
+    /// `"tcp"`.
+    pub fn scheme(self) -> &'static str {
+        match self {
+            Transport::Tcp => "tcp",
+            Transport::Ipc => "ipc",
+            Transport::Inproc => "inproc",
+            Transport::Udp => "udp",
+            Transport::Ws => "ws",
+            Transport::Wss => "wss",
+            Transport::Pgm => "pgm",
+            Transport::Epgm => "epgm",
+            Transport::Norm => "norm",
+            Transport::Tipc => "tipc",
+            Transport::Vmci => "vmci",
+        }
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.scheme())
+    }
+}
+
+/// The port of a network endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Port {
+    /// A fixed port number.
+    Number(u16),
+    /// A port chosen by the operating system when binding, written as
+    /// `*`.
+    Wildcard,
+}
+
+impl fmt::Display for Port {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Port::Number(port) => write!(f, "{}", port),
+            Port::Wildcard => f.write_str("*"),
+        }
+    }
+}
+
+/// An error that occurred while parsing an `Endpoint`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseEndpointError {
+    endpoint: String,
+    reason: &'static str,
+    error: Error,
+}
+
+impl ParseEndpointError {
+    fn new(endpoint: &str, reason: &'static str) -> ParseEndpointError {
+        ParseEndpointError {
+            endpoint: endpoint.to_owned(),
+            reason,
+            error: Error::EINVAL,
+        }
+    }
+
+    /// The endpoint that was rejected.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Describes why the endpoint was rejected.
+    pub fn reason(&self) -> &'static str {
+        self.reason
+    }
+}
+
+impl fmt::Display for ParseEndpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid endpoint {:?}: {}", self.endpoint, self.reason)
+    }
+}
+
+impl std::error::Error for ParseEndpointError {}
+
+/// Invalid endpoints are reported with the same error libzmq would
+/// return for them: `EPROTONOSUPPORT` for unknown transports, and
+/// `EINVAL` otherwise.
+impl From<ParseEndpointError> for Error {
+    fn from(error: ParseEndpointError) -> Self {
+        error.error
+    }
+}
+
+/// A validated 0MQ endpoint address, such as `tcp://127.0.0.1:5555`.
+///
+/// Parsing checks the syntax of the endpoint for the given transport,
+/// catching typos and embedded NUL bytes before they reach libzmq. It
+/// only rejects endpoints that libzmq would reject as well, and never
+/// changes them; whether the endpoint can actually be bound or
+/// connected to is only known once that is tried. The following forms
+/// are understood:
+///
+/// - `tcp://host:port`, where `host` may be a host name, an IPv4
+///   address, an IPv6 address (optionally in brackets), an interface
+///   name, or `*`, and `port` is a number or `*` for an ephemeral port.
+///   A source address may be given as `tcp://source;host:port`.
+/// - `udp://host:port`, optionally with a `source;` prefix.
+/// - `ws://host:port/path` and `wss://host:port/path`, where the path
+///   is optional.
+/// - `pgm://interface;group:port` and `epgm://interface;group:port`.
+/// - `ipc://path`, including `ipc://*` and `ipc://@abstract-name`.
+/// - `inproc://name`, `tipc://...`, `norm://...` and `vmci://...`,
+///   which are only checked for not being empty.
+///
+/// Socket methods such as `Socket::bind()` parse the endpoint strings
+/// they are given into an `Endpoint` this way.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Endpoint {
+    transport: Transport,
+    // The endpoint as given, including the transport.
+    endpoint: String,
+    // Offset of the address within `endpoint`.
+    address_start: usize,
+    source: Option<String>,
+    host: Option<String>,
+    port: Option<Port>,
+    path: Option<String>,
+    c_str: CString,
+}
+
+impl Endpoint {
+    /// Parse and validate an endpoint.
+    pub fn parse(endpoint: &str) -> Result<Endpoint, ParseEndpointError> {
+        let err = |reason| ParseEndpointError::new(endpoint, reason);
+
+        if endpoint.contains('\0') {
+            return Err(err("contains a NUL byte"));
+        }
+        let (scheme, address) = match endpoint.find("://") {
+            Some(pos) => (&endpoint[..pos], &endpoint[pos + 3..]),
+            None => return Err(err("missing `://` after the transport")),
+        };
+        let transport = Transport::from_scheme(scheme).ok_or_else(|| ParseEndpointError {
+            error: Error::EPROTONOSUPPORT,
+            ..err("unknown transport")
+        })?;
+        if address.is_empty() {
+            return Err(err("empty address"));
+        }
+
+        let mut source = None;
+        let mut host = None;
+        let mut port = None;
+        let mut path = None;
+
+        match transport {
+            Transport::Tcp | Transport::Udp | Transport::Pgm | Transport::Epgm => {
+                let target = match address.rfind(';') {
+                    Some(pos) => {
+                        let src = &address[..pos];
+                        if src.is_empty() {
+                            return Err(err("empty source address"));
+                        }
+                        validate_source(src).map_err(err)?;
+                        source = Some(src.to_owned());
+                        &address[pos + 1..]
+                    }
+                    None => {
+                        if transport == Transport::Pgm || transport == Transport::Epgm {
+                            return Err(err("missing `interface;` prefix"));
+                        }
+                        address
+                    }
+                };
+                let (h, p) = split_host_port(target).map_err(err)?;
+                host = Some(h.to_owned());
+                port = Some(p);
+            }
+            Transport::Ws | Transport::Wss => {
+                let (host_port, p) = match address.find('/') {
+                    Some(pos) => (&address[..pos], Some(&address[pos..])),
+                    None => (address, None),
+                };
+                let (h, hp) = split_host_port(host_port).map_err(err)?;
+                host = Some(h.to_owned());
+                port = Some(hp);
+                path = p.map(String::from);
+            }
+            Transport::Ipc => {
+                if address == "@" {
+                    return Err(err("empty abstract socket name"));
+                }
+            }
+            Transport::Inproc | Transport::Norm | Transport::Tipc | Transport::Vmci => {}
+        }
+
+        Ok(Endpoint {
+            transport,
+            address_start: scheme.len() + 3,
+            // Checked for NUL bytes above.
+            c_str: CString::new(endpoint.as_bytes()).unwrap(),
+            endpoint: endpoint.to_owned(),
+            source,
+            host,
+            port,
+            path,
+        })
+    }
+
+    /// The transport of the endpoint.
+    pub fn transport(&self) -> Transport {
+        self.transport
+    }
+
+    /// The transport-specific address, i.e. everything after `://`.
+    pub fn address(&self) -> &str {
+        &self.endpoint[self.address_start..]
+    }
+
+    /// The source address part (before `;`) of `tcp://`, `udp://`,
+    /// `pgm://` and `epgm://` endpoints, if present.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// The host part of a network endpoint, without brackets for IPv6
+    /// addresses. `*` denotes all interfaces.
+    pub fn host(&self) -> Option<&str> {
+        self.host
+            .as_deref()
+            .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+    }
+
+    /// The port of a network endpoint.
+    pub fn port(&self) -> Option<Port> {
+        self.port
+    }
+
+    /// The resource path of a `ws://` or `wss://` endpoint, if present.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Return true if binding to this endpoint lets the operating
+    /// system or libzmq choose the actual address, i.e. for the `*`
+    /// port, and for `ipc://*`.
+    pub fn is_wildcard(&self) -> bool {
+        self.port == Some(Port::Wildcard)
+            || (self.transport == Transport::Ipc && self.address() == "*")
+    }
+
+    /// The address of a `tcp://`, `udp://`, `ws://` or `wss://`
+    /// endpoint as a `SocketAddr`, if its host is an IP address and its
+    /// port is a number.
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        let ip = self.host()?.parse::<IpAddr>().ok()?;
+        match self.port? {
+            Port::Number(port) => Some(SocketAddr::new(ip, port)),
+            Port::Wildcard => None,
+        }
+    }
+
+    /// Return an endpoint that can be connected to, for an endpoint
+    /// that was bound to all interfaces.
+    ///
+    /// A `*`, `0.0.0.0` or `::` host is replaced with the matching
+    /// loopback address; all other endpoints are returned unchanged.
+    /// This is useful with the resolved endpoint of a wildcard bind, as
+    /// returned by `Socket::get_last_endpoint()`:
+    ///
+    /// ```
+    /// let ep = zmq::Endpoint::parse("tcp://0.0.0.0:5555").unwrap();
+    /// assert_eq!(ep.connectable().as_str(), "tcp://127.0.0.1:5555");
+    /// ```
+    pub fn connectable(&self) -> Endpoint {
+        let loopback = match self.host.as_deref() {
+            Some("*") | Some("0.0.0.0") => "127.0.0.1",
+            Some("[::]") | Some("::") => "[::1]",
+            _ => return self.clone(),
+        };
+        let mut endpoint = format!("{}://", self.transport);
+        if let Some(ref source) = self.source {
+            endpoint.push_str(source);
+            endpoint.push(';');
+        }
+        endpoint.push_str(loopback);
+        if let Some(port) = self.port {
+            endpoint.push_str(&format!(":{}", port));
+        }
+        if let Some(ref path) = self.path {
+            endpoint.push_str(path);
+        }
+        Endpoint::parse(&endpoint).expect("connectable endpoint must be valid")
+    }
+
+    /// The endpoint as a string, as passed to libzmq.
+    pub fn as_str(&self) -> &str {
+        &self.endpoint
+    }
+
+    pub(crate) fn as_c_str(&self) -> &CString {
+        &self.c_str
+    }
+}
+
+fn validate_source(source: &str) -> Result<(), &'static str> {
+    // The source may or may not carry a port, depending on the transport.
+    match split_host_port(source) {
+        Ok(_) => Ok(()),
+        Err(_) => validate_host(source),
+    }
+}
+
+fn split_host_port(address: &str) -> Result<(&str, Port), &'static str> {
+    let pos = address.rfind(':').ok_or("missing `:port`")?;
+    let (host, port) = (&address[..pos], &address[pos + 1..]);
+    validate_host(host)?;
+    let port = match port {
+        "*" => Port::Wildcard,
+        _ => {
+            if port.is_empty() || !port.bytes().all(|b| b.is_ascii_digit()) {
+                return Err("port must be a number or `*`");
+            }
+            Port::Number(port.parse().map_err(|_| "port out of range")?)
+        }
+    };
+    Ok((host, port))
+}
+
+/// Check the host of a network endpoint. Host names and interface names
+/// are left for libzmq to resolve, so only bracketed IPv6 addresses,
+/// which libzmq parses itself, are checked in detail.
+fn validate_host(host: &str) -> Result<(), &'static str> {
+    if host.is_empty() {
+        return Err("empty host");
+    }
+    if let Some(inner) = host.strip_prefix('[') {
+        let inner = inner.strip_suffix(']').ok_or("unterminated `[`")?;
+        // Allow for a zone index, e.g. `[fe80::1%eth0]`.
+        let addr = inner.split('%').next().unwrap_or("");
+        return addr
+            .parse::<std::net::Ipv6Addr>()
+            .map(|_| ())
+            .map_err(|_| "invalid IPv6 address");
+    }
+    Ok(())
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.endpoint)
+    }
+}
+
+impl fmt::Debug for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Endpoint({:?})", self.endpoint)
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = ParseEndpointError;
+
+    fn from_str(s: &str) -> Result<Endpoint, ParseEndpointError> {
+        Endpoint::parse(s)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Endpoint {
+    type Error = ParseEndpointError;
+
+    fn try_from(s: &'a str) -> Result<Endpoint, ParseEndpointError> {
+        Endpoint::parse(s)
+    }
+}
+
+impl<'a> TryFrom<&'a String> for Endpoint {
+    type Error = ParseEndpointError;
+
+    fn try_from(s: &'a String) -> Result<Endpoint, ParseEndpointError> {
+        Endpoint::parse(s)
+    }
+}
+
+impl TryFrom<String> for Endpoint {
+    type Error = ParseEndpointError;
+
+    fn try_from(s: String) -> Result<Endpoint, ParseEndpointError> {
+        Endpoint::parse(&s)
+    }
+}
+
+/// Convert a socket address into a `tcp://` endpoint.
+impl From<SocketAddr> for Endpoint {
+    fn from(addr: SocketAddr) -> Endpoint {
+        Endpoint::parse(&format!("tcp://{}", addr))
+            .expect("socket address must be a valid endpoint")
+    }
+}
+
+impl AsRef<str> for Endpoint {
+    fn as_ref(&self) -> &str {
+        &self.endpoint
+    }
+}
@@ -0,0 +1,176 @@
+//! Errors annotated with the operation that caused them.
+
+use std::fmt;
+use std::result;
+
+use crate::{Error, Socket, SocketType};
+
+/// A socket operation, as recorded by `ErrorContext`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation {
+    /// `Socket::bind()` to the given endpoint.
+    Bind(String),
+    /// `Socket::unbind()` from the given endpoint.
+    Unbind(String),
+    /// `Socket::connect()` to the given endpoint.
+    Connect(String),
+    /// `Socket::disconnect()` from the given endpoint.
+    Disconnect(String),
+    /// Setting the socket option with the given name, e.g.
+    /// `"ZMQ_LINGER"`.
+    SetSockOpt(&'static str),
+    /// Getting the socket option with the given name.
+    GetSockOpt(&'static str),
+    /// Sending a message.
+    SendMessage,
+    /// Receiving a message.
+    RecvMessage,
+    /// An operation that was not recorded by the socket, e.g. because
+    /// the error did not come from one of its methods.
+    Unknown,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Operation::Bind(ref endpoint) => write!(f, "bind to {}", endpoint),
+            Operation::Unbind(ref endpoint) => write!(f, "unbind from {}", endpoint),
+            Operation::Connect(ref endpoint) => write!(f, "connect to {}", endpoint),
+            Operation::Disconnect(ref endpoint) => write!(f, "disconnect from {}", endpoint),
+            Operation::SetSockOpt(option) => write!(f, "setting {}", option),
+            Operation::GetSockOpt(option) => write!(f, "getting {}", option),
+            Operation::SendMessage => write!(f, "send"),
+            Operation::RecvMessage => write!(f, "recv"),
+            Operation::Unknown => write!(f, "operation"),
+        }
+    }
+}
+
+/// An `Error` together with the operation that caused it, and the
+/// type of the socket it was performed on.
+///
+/// This is an opt-in alternative to the plain `Error` type, which
+/// only carries the error number. Sockets record the operations that
+/// fail on them, so `ResultExt::with_socket()` can attach the context
+/// to the result of a socket method:
+///
+/// ```
+/// use zmq::{Operation, ResultExt};
+///
+/// let ctx = zmq::Context::new();
+/// let socket = ctx.socket(zmq::ROUTER).unwrap();
+/// let err = socket
+///     .connect("bogus://endpoint")
+///     .with_socket(&socket)
+///     .unwrap_err();
+/// assert_eq!(err.error(), zmq::Error::EPROTONOSUPPORT);
+/// assert_eq!(err.operation(), &Operation::Connect("bogus://endpoint".into()));
+/// assert_eq!(err.socket_type(), Some(zmq::ROUTER));
+/// println!("{}", err); // "connect to bogus://endpoint on ROUTER socket failed: ..."
+/// ```
+///
+/// `ResultExt::for_operation()` names the operation explicitly instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorContext {
+    error: Error,
+    operation: Operation,
+    socket_type: Option<SocketType>,
+}
+
+impl ErrorContext {
+    /// Annotate `error` with the operation that caused it on `socket`.
+    pub fn new(error: Error, socket: &Socket, operation: Operation) -> ErrorContext {
+        ErrorContext {
+            error,
+            operation,
+            // This may fail, e.g. after the context has been shut
+            // down; the socket type is not essential.
+            socket_type: socket.socket_type_unrecorded().ok(),
+        }
+    }
+
+    /// The underlying 0MQ error.
+    pub fn error(&self) -> Error {
+        self.error
+    }
+
+    /// The failed operation.
+    pub fn operation(&self) -> &Operation {
+        &self.operation
+    }
+
+    /// The type of the socket the operation was performed on, if it
+    /// could be determined.
+    pub fn socket_type(&self) -> Option<SocketType> {
+        self.socket_type
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.socket_type {
+            Some(socket_type) => write!(
+                f,
+                "{} on {:?} socket failed: {}",
+                self.operation, socket_type, self.error
+            ),
+            None => write!(f, "{} failed: {}", self.operation, self.error),
+        }
+    }
+}
+
+impl std::error::Error for ErrorContext {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<ErrorContext> for Error {
+    fn from(error: ErrorContext) -> Self {
+        error.error
+    }
+}
+
+impl From<ErrorContext> for std::io::Error {
+    fn from(error: ErrorContext) -> Self {
+        std::io::Error::new(error.error.io_error_kind(), error)
+    }
+}
+
+/// Extension trait for attaching an `ErrorContext` to the result of a
+/// socket operation.
+pub trait ResultExt<T> {
+    /// Turn an `Err(Error)` into an `Err(ErrorContext)` describing the
+    /// operation that failed on `socket`.
+    ///
+    /// This must be called before other operations on `socket` fail,
+    /// as only the last failure is recorded. If the error does not
+    /// match it, the operation is reported as `Operation::Unknown`.
+    fn with_socket(self, socket: &Socket) -> result::Result<T, ErrorContext>;
+
+    /// Turn an `Err(Error)` into an `Err(ErrorContext)` describing
+    /// `operation` on `socket`.
+    fn for_operation(
+        self,
+        socket: &Socket,
+        operation: Operation,
+    ) -> result::Result<T, ErrorContext>;
+}
+
+impl<T> ResultExt<T> for result::Result<T, Error> {
+    fn with_socket(self, socket: &Socket) -> result::Result<T, ErrorContext> {
+        self.map_err(|error| match socket.last_error() {
+            Some(context) if context.error == error => context,
+            _ => ErrorContext::new(error, socket, Operation::Unknown),
+        })
+    }
+
+    fn for_operation(
+        self,
+        socket: &Socket,
+        operation: Operation,
+    ) -> result::Result<T, ErrorContext> {
+        self.map_err(|error| ErrorContext::new(error, socket, operation))
+    }
+}
@@ -0,0 +1,168 @@
+//! Integration with the `mio` event loop.
+
+use std::cell::Cell;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::result;
+
+use mio::event::Source;
+use mio::unix::SourceFd;
+use mio::{Interest, Registry, Token};
+
+use crate::{Message, PollEvents, Result, Sendable, Socket};
+
+/// A socket for use with a mio event loop.
+///
+/// mio only reports readiness of the socket's `ZMQ_FD` once, and only
+/// on a change of state. After sending or receiving a message, libzmq
+/// may have consumed that change without mio ever seeing it, so the
+/// socket may be ready even though no new event arrives. This wrapper
+/// tracks that case:
+///
+/// - Call `ready()` whenever mio reports an event for the socket's
+///   token, and process the socket until it no longer returns the
+///   events of interest.
+/// - Before blocking in `mio::Poll::poll()`, check `needs_recheck()`;
+///   if it returns true, call `ready()` first, as no event would be
+///   reported for the current state.
+///
+/// Only `EventedSocket` can be registered with mio, not a plain
+/// `Socket`, so that every send and receive goes through this
+/// tracking. Registering the socket's `ZMQ_FD` with mio directly, e.g.
+/// via `mio::unix::SourceFd`, bypasses it.
+///
+/// ```
+/// use mio::{Events, Interest, Poll, Token};
+/// use zmq::EventedSocket;
+///
+/// let ctx = zmq::Context::new();
+/// let mut pull = EventedSocket::new(ctx.socket(zmq::PULL).unwrap());
+/// pull.get_ref().bind("inproc://evented-doc").unwrap();
+/// let push = ctx.socket(zmq::PUSH).unwrap();
+/// push.connect("inproc://evented-doc").unwrap();
+/// push.send("hello", 0).unwrap();
+///
+/// let mut poll = Poll::new().unwrap();
+/// let mut events = Events::with_capacity(8);
+/// poll.registry()
+///     .register(&mut pull, Token(0), Interest::READABLE)
+///     .unwrap();
+/// loop {
+///     if !pull.needs_recheck() {
+///         poll.poll(&mut events, None).unwrap();
+///     }
+///     if pull.ready().unwrap().contains(zmq::POLLIN) {
+///         assert_eq!(pull.recv_bytes(0).unwrap(), b"hello");
+///         break;
+///     }
+/// }
+/// ```
+pub struct EventedSocket {
+    socket: Socket,
+    recheck: Cell<bool>,
+}
+
+impl EventedSocket {
+    /// Wrap `socket` for use with mio.
+    pub fn new(socket: Socket) -> EventedSocket {
+        EventedSocket {
+            socket,
+            // Events may already be pending before registration.
+            recheck: Cell::new(true),
+        }
+    }
+
+    /// Get a reference to the underlying socket.
+    ///
+    /// As the socket may be used to send or receive messages through
+    /// this reference, `needs_recheck()` returns true afterwards.
+    pub fn get_ref(&self) -> &Socket {
+        self.recheck.set(true);
+        &self.socket
+    }
+
+    /// Return the underlying socket.
+    pub fn into_inner(self) -> Socket {
+        self.socket
+    }
+
+    /// Return true if `ready()` must be called before waiting for the
+    /// next readiness event.
+    pub fn needs_recheck(&self) -> bool {
+        self.recheck.get()
+    }
+
+    /// Return the events the socket is ready for, re-arming the
+    /// edge-triggered readiness notification.
+    pub fn ready(&self) -> Result<PollEvents> {
+        let events = self.socket.get_events()?;
+        self.recheck.set(false);
+        Ok(events)
+    }
+
+    /// Send a message, see `Socket::send()`.
+    pub fn send<T>(&self, data: T, flags: i32) -> Result<()>
+    where
+        T: Sendable,
+    {
+        self.recheck.set(true);
+        self.socket.send(data, flags)
+    }
+
+    /// Send a multipart message, see `Socket::send_multipart()`.
+    pub fn send_multipart<I, T>(&self, iter: I, flags: i32) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Message>,
+    {
+        self.recheck.set(true);
+        self.socket.send_multipart(iter, flags)
+    }
+
+    /// Receive a message into `msg`, see `Socket::recv()`.
+    pub fn recv(&self, msg: &mut Message, flags: i32) -> Result<()> {
+        self.recheck.set(true);
+        self.socket.recv(msg, flags)
+    }
+
+    /// Receive a message as a byte vector, see `Socket::recv_bytes()`.
+    pub fn recv_bytes(&self, flags: i32) -> Result<Vec<u8>> {
+        self.recheck.set(true);
+        self.socket.recv_bytes(flags)
+    }
+
+    /// Receive a `String`, see `Socket::recv_string()`.
+    pub fn recv_string(&self, flags: i32) -> Result<result::Result<String, Vec<u8>>> {
+        self.recheck.set(true);
+        self.socket.recv_string(flags)
+    }
+
+    /// Receive a multipart message, see `Socket::recv_multipart()`.
+    pub fn recv_multipart(&self, flags: i32) -> Result<Vec<Vec<u8>>> {
+        self.recheck.set(true);
+        self.socket.recv_multipart(flags)
+    }
+}
+
+/// Registers the socket's `ZMQ_FD` with mio.
+///
+/// The file descriptor only signals that the state of the socket has
+/// changed, in an edge-triggered fashion, and it always does so by
+/// becoming readable. It is therefore registered for readability,
+/// whatever interest is given; use `ready()` to find out what the
+/// socket is actually ready for.
+impl Source for EventedSocket {
+    fn register(&mut self, registry: &Registry, token: Token, _: Interest) -> io::Result<()> {
+        self.recheck.set(true);
+        SourceFd(&self.socket.as_raw_fd()).register(registry, token, Interest::READABLE)
+    }
+
+    fn reregister(&mut self, registry: &Registry, token: Token, _: Interest) -> io::Result<()> {
+        self.recheck.set(true);
+        SourceFd(&self.socket.as_raw_fd()).reregister(registry, token, Interest::READABLE)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        SourceFd(&self.socket.as_raw_fd()).deregister(registry)
+    }
+}
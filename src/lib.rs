@@ -4,18 +4,26 @@
 
 use bitflags::bitflags;
 use libc::{c_int, c_long, c_short};
+use log::error;
 
+use std::cell::Cell;
+use std::convert::TryFrom;
 use std::ffi;
 use std::fmt;
 use std::marker::PhantomData;
+use std::net::SocketAddr;
 use std::os::raw::c_void;
 #[cfg(unix)]
-use std::os::unix::io::{AsRawFd, RawFd as UnixRawFd};
+use std::os::unix::io::{
+    AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd as UnixRawFd,
+};
 #[cfg(windows)]
-use std::os::windows::io::{AsRawSocket, RawSocket};
+use std::os::windows::io::{AsRawSocket, AsSocket, BorrowedSocket, RawSocket};
 use std::result;
 use std::string::FromUtf8Error;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, Weak};
+use std::time::Duration;
 use std::{mem, ptr, str};
 
 use zmq_sys::{errno, RawFd};
@@ -30,19 +38,60 @@ macro_rules! zmq_try {
     }}
 }
 
+mod actor;
+#[cfg(all(unix, any(feature = "tokio", feature = "async-io")))]
+mod async_socket;
+mod beacon;
+pub mod bstar;
+pub mod clone;
+mod endpoint;
+mod error_context;
+#[cfg(all(unix, feature = "mio"))]
+mod evented;
+pub mod mdp;
 mod message;
+mod monitor;
+mod pipe;
+pub mod pirate;
+mod proxy;
+mod registry;
+mod reliable;
 mod sockopt;
-
+#[cfg(unix)]
+mod systemd;
+
+pub use crate::actor::Actor;
+#[cfg(all(unix, any(feature = "tokio", feature = "async-io")))]
+pub use crate::async_socket::{proxy_async, AsyncMonitor, AsyncSocket, ProxyAsync};
+pub use crate::beacon::{Beacon, BEACON_MAX};
+pub use crate::endpoint::{Endpoint, ParseEndpointError, Port, Transport};
+pub use crate::error_context::{ErrorContext, Operation, ResultExt};
+#[cfg(all(unix, feature = "mio"))]
+pub use crate::evented::EventedSocket;
 use crate::message::msg_ptr;
 pub use crate::message::Message;
+pub use crate::monitor::MonitorEvent;
+pub use crate::proxy::{
+    spawn_proxy, spawn_proxy_steerable, Proxy, ProxyAction, ProxyController, ProxyHandle,
+    ProxyStats,
+};
+pub use crate::registry::SocketInfo;
+use crate::registry::SocketRegistry;
+#[cfg(unix)]
+pub use crate::systemd::listen_fds;
 pub use crate::SocketType::*;
 
 /// `zmq`-specific Result type.
 pub type Result<T> = result::Result<T, Error>;
 
 /// Socket types
+///
+/// Socket types unknown to this binding, such as the draft socket
+/// types of libzmq, are rejected by the `TryFrom<i32>` conversion
+/// instead of being represented here.
 #[allow(non_camel_case_types)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum SocketType {
     PAIR,
     PUB,
@@ -76,8 +125,15 @@ impl SocketType {
         };
         raw as c_int
     }
-    fn from_raw(raw: c_int) -> SocketType {
-        match raw as u32 {
+}
+
+impl TryFrom<i32> for SocketType {
+    type Error = Error;
+
+    /// Convert a libzmq socket type constant, failing with `EINVAL`
+    /// for socket types not known to this binding.
+    fn try_from(raw: i32) -> Result<SocketType> {
+        let socket_type = match raw as u32 {
             zmq_sys::ZMQ_PAIR => PAIR,
             zmq_sys::ZMQ_PUB => PUB,
             zmq_sys::ZMQ_SUB => SUB,
@@ -90,14 +146,22 @@ impl SocketType {
             zmq_sys::ZMQ_XPUB => XPUB,
             zmq_sys::ZMQ_XSUB => XSUB,
             zmq_sys::ZMQ_STREAM => STREAM,
-            _ => panic!("socket type is out of range!"),
-        }
+            _ => return Err(Error::EINVAL),
+        };
+        Ok(socket_type)
     }
 }
 
 /// Socket Events
+///
+/// The variants have the values of the corresponding libzmq constants,
+/// so they can be cast to an integer, e.g. for `Socket::monitor()`.
+/// Events not known to this binding, e.g. ones introduced by a newer
+/// libzmq, are represented by the `Unknown` variant, whose value is 0;
+/// `MonitorEvent::raw_event()` gives access to the raw event number.
 #[allow(non_camel_case_types)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum SocketEvent {
     // TODO: This should become a proper enum, including the data.
     CONNECTED = zmq_sys::ZMQ_EVENT_CONNECTED as isize,
@@ -116,6 +180,7 @@ pub enum SocketEvent {
     HANDSHAKE_FAILED_PROTOCOL = zmq_sys::ZMQ_EVENT_HANDSHAKE_FAILED_PROTOCOL as isize,
     HANDSHAKE_FAILED_AUTH = zmq_sys::ZMQ_EVENT_HANDSHAKE_FAILED_AUTH as isize,
     ALL = zmq_sys::ZMQ_EVENT_ALL as isize,
+    Unknown = 0,
 }
 
 impl SocketEvent {
@@ -143,11 +208,17 @@ impl SocketEvent {
             zmq_sys::ZMQ_EVENT_HANDSHAKE_FAILED_PROTOCOL => HANDSHAKE_FAILED_PROTOCOL,
             zmq_sys::ZMQ_EVENT_HANDSHAKE_FAILED_AUTH => HANDSHAKE_FAILED_AUTH,
             zmq_sys::ZMQ_EVENT_ALL => ALL,
-            x => panic!("unknown event type {}", x),
+            _ => Unknown,
         }
     }
 }
 
+impl From<u16> for SocketEvent {
+    fn from(raw: u16) -> SocketEvent {
+        SocketEvent::from_raw(raw)
+    }
+}
+
 /// Flag for socket `send` methods that specifies non-blocking mode.
 pub static DONTWAIT: i32 = zmq_sys::ZMQ_DONTWAIT as i32;
 /// Flag for socket `send` methods that specifies that more frames of a
@@ -157,6 +228,7 @@ pub static SNDMORE: i32 = zmq_sys::ZMQ_SNDMORE as i32;
 /// Security Mechanism
 #[allow(non_camel_case_types)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Mechanism {
     // TODO: Fix the naming
     ZMQ_NULL,
@@ -165,8 +237,29 @@ pub enum Mechanism {
     ZMQ_GSSAPI,
 }
 
+impl TryFrom<i32> for Mechanism {
+    type Error = Error;
+
+    /// Convert a libzmq security mechanism constant, failing with
+    /// `EINVAL` for mechanisms not known to this binding.
+    fn try_from(raw: i32) -> Result<Mechanism> {
+        let mechanism = match raw as u32 {
+            zmq_sys::ZMQ_NULL => Mechanism::ZMQ_NULL,
+            zmq_sys::ZMQ_PLAIN => Mechanism::ZMQ_PLAIN,
+            zmq_sys::ZMQ_CURVE => Mechanism::ZMQ_CURVE,
+            zmq_sys::ZMQ_GSSAPI => Mechanism::ZMQ_GSSAPI,
+            _ => return Err(Error::EINVAL),
+        };
+        Ok(mechanism)
+    }
+}
+
 /// An error returned by a 0MQ API function.
+///
+/// Error codes not known to this binding are represented by the
+/// `Unknown` variant, which holds the raw error number.
 #[derive(Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum Error {
     EACCES,
     EADDRINUSE,
@@ -192,12 +285,20 @@ pub enum Error {
     ENOBUFS,
     ENETDOWN,
     EADDRNOTAVAIL,
+    EAFNOSUPPORT,
+    ENETUNREACH,
+    ECONNABORTED,
+    ECONNRESET,
+    ETIMEDOUT,
+    ENETRESET,
 
     // native zmq error codes
     EFSM,
     ENOCOMPATPROTO,
     ETERM,
     EMTHREAD,
+
+    Unknown(i32),
 }
 
 impl Error {
@@ -227,11 +328,19 @@ impl Error {
             Error::ENOBUFS => errno::ENOBUFS,
             Error::ENETDOWN => errno::ENETDOWN,
             Error::EADDRNOTAVAIL => errno::EADDRNOTAVAIL,
+            Error::EAFNOSUPPORT => errno::EAFNOSUPPORT,
+            Error::ENETUNREACH => errno::ENETUNREACH,
+            Error::ECONNABORTED => errno::ECONNABORTED,
+            Error::ECONNRESET => errno::ECONNRESET,
+            Error::ETIMEDOUT => errno::ETIMEDOUT,
+            Error::ENETRESET => errno::ENETRESET,
 
             Error::EFSM => errno::EFSM,
             Error::ENOCOMPATPROTO => errno::ENOCOMPATPROTO,
             Error::ETERM => errno::ETERM,
             Error::EMTHREAD => errno::EMTHREAD,
+
+            Error::Unknown(raw) => raw,
         }
     }
 
@@ -261,6 +370,12 @@ impl Error {
             errno::ENETDOWN => Error::ENETDOWN,
             errno::EADDRNOTAVAIL => Error::EADDRNOTAVAIL,
             errno::EINTR => Error::EINTR,
+            errno::EAFNOSUPPORT => Error::EAFNOSUPPORT,
+            errno::ENETUNREACH => Error::ENETUNREACH,
+            errno::ECONNABORTED => Error::ECONNABORTED,
+            errno::ECONNRESET => Error::ECONNRESET,
+            errno::ETIMEDOUT => Error::ETIMEDOUT,
+            errno::ENETRESET => Error::ENETRESET,
 
             // These may turn up on platforms that don't support these
             // errno codes natively (Windows)
@@ -274,18 +389,14 @@ impl Error {
             errno::EINPROGRESS_ALT => Error::EINPROGRESS,
             errno::ENOTSOCK_ALT => Error::ENOTSOCK,
             errno::EMSGSIZE_ALT => Error::EMSGSIZE,
-
-            // TODO: these are present in `zmq-sys`, but not handled, as that
-            // would break backwards-compatibility for the `Error` enum.
-
-            // errno::EAFNOSUPPORT_ALT => Error::EAFNOSUPPORT,
-            // errno::ENETUNREACH_ALT => Error::ENETUNREACH,
-            // errno::ECONNABORTED_ALT => Error::ECONNABORTED,
-            // errno::ECONNRESET_ALT => Error::ECONNRESET,
-            // errno::ENOTCONN_ALT => Error::ENOTCONN,
-            // errno::ETIMEDOUT_ALT => Error::ETIMEDOUT,
-            // errno::EHOSTUNREACH_ALT => Error::EHOSTUNREACH,
-            // errno::ENETRESET_ALT => Error::ENETRESET,
+            errno::EAFNOSUPPORT_ALT => Error::EAFNOSUPPORT,
+            errno::ENETUNREACH_ALT => Error::ENETUNREACH,
+            errno::ECONNABORTED_ALT => Error::ECONNABORTED,
+            errno::ECONNRESET_ALT => Error::ECONNRESET,
+            errno::ENOTCONN_ALT => Error::ENOTCONN,
+            errno::ETIMEDOUT_ALT => Error::ETIMEDOUT,
+            errno::EHOSTUNREACH_ALT => Error::EHOSTUNREACH,
+            errno::ENETRESET_ALT => Error::ENETRESET,
 
             // 0MQ native error codes
             errno::EFSM => Error::EFSM,
@@ -293,14 +404,7 @@ impl Error {
             errno::ETERM => Error::ETERM,
             errno::EMTHREAD => Error::EMTHREAD,
 
-            x => unsafe {
-                let s = zmq_sys::zmq_strerror(x);
-                panic!(
-                    "unknown error [{}]: {}",
-                    x,
-                    str::from_utf8(ffi::CStr::from_ptr(s).to_bytes()).unwrap()
-                )
-            },
+            x => Error::Unknown(x),
         }
     }
 
@@ -309,7 +413,7 @@ impl Error {
         unsafe {
             let s = zmq_sys::zmq_strerror(self.to_raw());
             let v: &'static [u8] = mem::transmute(ffi::CStr::from_ptr(s).to_bytes());
-            str::from_utf8(v).unwrap()
+            str::from_utf8(v).unwrap_or("unknown error")
         }
     }
 }
@@ -333,27 +437,40 @@ impl fmt::Debug for Error {
     }
 }
 
-impl From<Error> for std::io::Error {
-    fn from(error: Error) -> Self {
+impl Error {
+    fn io_error_kind(self) -> std::io::ErrorKind {
         use std::io::ErrorKind;
 
-        let kind = match error {
+        // The kinds for EHOSTUNREACH, ENETUNREACH, ENETDOWN, EBUSY and
+        // ENAMETOOLONG are newer than our minimum supported Rust
+        // version, so these errors map to `Other` for now.
+        match self {
             Error::ENOENT => ErrorKind::NotFound,
             Error::EACCES => ErrorKind::PermissionDenied,
             Error::ECONNREFUSED => ErrorKind::ConnectionRefused,
+            Error::ECONNRESET => ErrorKind::ConnectionReset,
+            Error::ECONNABORTED => ErrorKind::ConnectionAborted,
             Error::ENOTCONN => ErrorKind::NotConnected,
             Error::EADDRINUSE => ErrorKind::AddrInUse,
             Error::EADDRNOTAVAIL => ErrorKind::AddrNotAvailable,
             Error::EAGAIN => ErrorKind::WouldBlock,
-            Error::EINVAL => ErrorKind::InvalidInput,
+            Error::EINVAL | Error::EFAULT => ErrorKind::InvalidInput,
             Error::EINTR => ErrorKind::Interrupted,
+            Error::ETIMEDOUT => ErrorKind::TimedOut,
+            Error::ENOMEM | Error::ENOBUFS => ErrorKind::OutOfMemory,
+            Error::ENOTSUP | Error::EPROTONOSUPPORT | Error::EAFNOSUPPORT => ErrorKind::Unsupported,
             _ => ErrorKind::Other,
-        };
+        }
+    }
+}
+
+impl From<Error> for std::io::Error {
+    fn from(error: Error) -> Self {
         // TODO: With rust 1.14 and up there is an optimization
         // opportunity using `std::io::Error: From<ErrorKind>` when
         // `kind != Other`. We should do that once 1.14 has been
         // stable for a bit.
-        std::io::Error::new(kind, error)
+        std::io::Error::new(error.io_error_kind(), error)
     }
 }
 
@@ -361,6 +478,11 @@ fn errno_to_error() -> Error {
     Error::from_raw(unsafe { zmq_sys::zmq_errno() })
 }
 
+/// Map an error from the OS to the equivalent 0MQ error.
+fn io_error_to_zmq(error: std::io::Error) -> Error {
+    error.raw_os_error().map_or(Error::EINVAL, Error::from_raw)
+}
+
 /// Return the current zeromq version, as `(major, minor, patch)`.
 pub fn version() -> (i32, i32, i32) {
     let mut major = 0;
@@ -376,13 +498,83 @@ pub fn version() -> (i32, i32, i32) {
 
 struct RawContext {
     ctx: *mut c_void,
+    /// Set by the first caller of `term()`, so that no other thread
+    /// starts using the context meanwhile.
+    terminating: AtomicBool,
+    /// Whether `zmq_ctx_term` has succeeded, after which `ctx` is
+    /// freed. All calls passing `ctx` to libzmq hold the read lock, and
+    /// check `terminating` while holding it, so once `term()` has taken
+    /// the write lock, nobody uses the context any more. The write lock
+    /// is never held while blocking, so that other threads can always
+    /// close their sockets.
+    terminated: RwLock<bool>,
+    registry: Option<SocketRegistry>,
 }
 
 impl RawContext {
+    /// Run `f` with the context pointer, failing with `ETERM` if the
+    /// context has been terminated, or is being terminated.
+    ///
+    /// `f` must not block, as terminating the context waits for it.
+    fn with_ctx<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(*mut c_void) -> Result<T>,
+    {
+        let terminated = self.terminated.read().unwrap_or_else(|e| e.into_inner());
+        if *terminated || self.terminating.load(Ordering::SeqCst) {
+            return Err(Error::ETERM);
+        }
+        f(self.ctx)
+    }
+
     fn term(&self) -> Result<()> {
-        zmq_try!(unsafe { zmq_sys::zmq_ctx_term(self.ctx) });
+        if !self.start_term() {
+            return Ok(());
+        }
+        self.finish_term()
+    }
+
+    /// Stop other threads from using the context, waiting for those
+    /// currently inside `with_ctx()`.
+    ///
+    /// Only one caller may terminate the context; for the others, it is
+    /// already (being) terminated, and this returns false.
+    fn start_term(&self) -> bool {
+        if self
+            .terminating
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return false;
+        }
+        drop(self.terminated.write().unwrap_or_else(|e| e.into_inner()));
+        true
+    }
+
+    /// Terminate the context after `start_term()`, blocking until all
+    /// of its sockets have been closed.
+    fn finish_term(&self) -> Result<()> {
+        if unsafe { zmq_sys::zmq_ctx_term(self.ctx) } == -1 {
+            // E.g. with `EINTR`, the context is still intact, and
+            // terminating it may be retried.
+            let e = errno_to_error();
+            self.terminating.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+        *self.terminated.write().unwrap_or_else(|e| e.into_inner()) = true;
         Ok(())
     }
+
+    fn shutdown(&self) -> Result<()> {
+        match self.with_ctx(|ctx| {
+            zmq_try!(unsafe { zmq_sys::zmq_ctx_shutdown(ctx) });
+            Ok(())
+        }) {
+            // Terminating the context shuts it down as well.
+            Err(Error::ETERM) => Ok(()),
+            result => result,
+        }
+    }
 }
 
 unsafe impl Send for RawContext {}
@@ -425,26 +617,147 @@ pub struct Context {
 impl Context {
     /// Create a new reference-counted context handle.
     pub fn new() -> Context {
+        Context::with_registry(None)
+    }
+
+    fn with_registry(registry: Option<SocketRegistry>) -> Context {
         Context {
             raw: Arc::new(RawContext {
                 ctx: unsafe { zmq_sys::zmq_ctx_new() },
+                terminating: AtomicBool::new(false),
+                terminated: RwLock::new(false),
+                registry,
             }),
         }
     }
 
+    /// Create a `ContextBuilder`, which allows configuring a context
+    /// before it is used to create sockets.
+    pub fn builder() -> ContextBuilder {
+        ContextBuilder::new()
+    }
+
+    fn get_option(&self, option: u32) -> Result<i32> {
+        self.raw.with_ctx(|ctx| {
+            let rc = zmq_try!(unsafe { zmq_sys::zmq_ctx_get(ctx, option as c_int) });
+            Ok(rc as i32)
+        })
+    }
+
+    fn set_option(&self, option: u32, value: i32) -> Result<()> {
+        self.raw.with_ctx(|ctx| {
+            zmq_try!(unsafe { zmq_sys::zmq_ctx_set(ctx, option as c_int, value as c_int) });
+            Ok(())
+        })
+    }
+
     /// Get the size of the ØMQ thread pool to handle I/O operations.
     pub fn get_io_threads(&self) -> Result<i32> {
-        let rc =
-            zmq_try!(unsafe { zmq_sys::zmq_ctx_get(self.raw.ctx, zmq_sys::ZMQ_IO_THREADS as _) });
-        Ok(rc as i32)
+        self.get_option(zmq_sys::ZMQ_IO_THREADS)
     }
 
     /// Set the size of the ØMQ thread pool to handle I/O operations.
     pub fn set_io_threads(&self, value: i32) -> Result<()> {
-        zmq_try!(unsafe {
-            zmq_sys::zmq_ctx_set(self.raw.ctx, zmq_sys::ZMQ_IO_THREADS as _, value)
-        });
-        Ok(())
+        self.set_option(zmq_sys::ZMQ_IO_THREADS, value)
+    }
+
+    /// Get the maximum number of sockets allowed on the context.
+    pub fn get_max_sockets(&self) -> Result<i32> {
+        self.get_option(zmq_sys::ZMQ_MAX_SOCKETS)
+    }
+
+    /// Set the maximum number of sockets allowed on the context.
+    ///
+    /// The value must not exceed the limit returned by
+    /// `get_socket_limit()`.
+    pub fn set_max_sockets(&self, value: i32) -> Result<()> {
+        self.set_option(zmq_sys::ZMQ_MAX_SOCKETS, value)
+    }
+
+    /// Get the largest number of sockets that `set_max_sockets()`
+    /// will accept.
+    pub fn get_socket_limit(&self) -> Result<i32> {
+        self.get_option(zmq_sys::ZMQ_SOCKET_LIMIT)
+    }
+
+    /// Set the scheduling priority of the context's internal threads.
+    ///
+    /// The meaning of the value depends on the scheduling policy
+    /// (see `set_thread_sched_policy()`) and on the operating
+    /// system. This option has no getter in libzmq, and only affects
+    /// threads started after it has been set.
+    pub fn set_thread_priority(&self, value: i32) -> Result<()> {
+        self.set_option(zmq_sys::ZMQ_THREAD_PRIORITY, value)
+    }
+
+    /// Set the scheduling policy of the context's internal threads,
+    /// such as `libc::SCHED_FIFO`.
+    ///
+    /// There is no corresponding getter, as libzmq reports the
+    /// default policy as `-1`, which is indistinguishable from an
+    /// error.
+    pub fn set_thread_sched_policy(&self, value: i32) -> Result<()> {
+        self.set_option(zmq_sys::ZMQ_THREAD_SCHED_POLICY, value)
+    }
+
+    /// Add a CPU to the set the context's internal threads are
+    /// pinned to.
+    ///
+    /// Initially the set is empty, which means the threads may run on
+    /// any CPU. Only affects threads started after the CPU is added.
+    pub fn add_thread_affinity_cpu(&self, cpu: i32) -> Result<()> {
+        self.set_option(zmq_sys::ZMQ_THREAD_AFFINITY_CPU_ADD, cpu)
+    }
+
+    /// Remove a CPU from the set the context's internal threads are
+    /// pinned to.
+    ///
+    /// Fails with `EINVAL` if the CPU has not been added before.
+    pub fn remove_thread_affinity_cpu(&self, cpu: i32) -> Result<()> {
+        self.set_option(zmq_sys::ZMQ_THREAD_AFFINITY_CPU_REMOVE, cpu)
+    }
+
+    /// Get the numeric prefix of the context's internal thread names.
+    pub fn get_thread_name_prefix(&self) -> Result<i32> {
+        self.get_option(zmq_sys::ZMQ_THREAD_NAME_PREFIX)
+    }
+
+    /// Set a numeric prefix for the context's internal thread names.
+    ///
+    /// The threads are then named `ZMQbg/<prefix>/IO/<n>` and so on,
+    /// which makes them easy to identify in tools like `perf` or
+    /// `top`.
+    pub fn set_thread_name_prefix(&self, value: i32) -> Result<()> {
+        self.set_option(zmq_sys::ZMQ_THREAD_NAME_PREFIX, value)
+    }
+
+    /// Get the maximum message size allowed by the context.
+    pub fn get_max_msgsz(&self) -> Result<i32> {
+        self.get_option(zmq_sys::ZMQ_MAX_MSGSZ)
+    }
+
+    /// Set the maximum message size allowed by the context.
+    pub fn set_max_msgsz(&self, value: i32) -> Result<()> {
+        self.set_option(zmq_sys::ZMQ_MAX_MSGSZ, value)
+    }
+
+    /// Get the size of the `zmq_msg_t` structure of the linked
+    /// libzmq.
+    pub fn get_msg_t_size(&self) -> Result<i32> {
+        self.get_option(zmq_sys::ZMQ_MSG_T_SIZE)
+    }
+
+    /// Return true if terminating the context blocks until all
+    /// pending messages have been sent, subject to each socket's
+    /// linger period.
+    pub fn is_blocky(&self) -> Result<bool> {
+        self.get_option(zmq_sys::ZMQ_BLOCKY).map(|value| value != 0)
+    }
+
+    /// Set whether terminating the context should block on pending
+    /// messages (the default).
+    pub fn set_blocky(&self, value: bool) -> Result<()> {
+        self.set_option(zmq_sys::ZMQ_BLOCKY, value as i32)
     }
 
     /// Create a new socket.
@@ -453,24 +766,188 @@ impl Context {
     /// the context it was created from, and will keep that context
     /// from being dropped while being live.
     pub fn socket(&self, socket_type: SocketType) -> Result<Socket> {
-        let sock = unsafe { zmq_sys::zmq_socket(self.raw.ctx, socket_type.to_raw()) };
-
-        if sock.is_null() {
-            return Err(errno_to_error());
-        }
+        let endpoints = Arc::default();
+        let (sock, fd) = self.raw.with_ctx(|ctx| {
+            let sock = unsafe { zmq_sys::zmq_socket(ctx, socket_type.to_raw()) };
+            if sock.is_null() {
+                return Err(errno_to_error());
+            }
+            let fd = match sockopt::get(sock, zmq_sys::ZMQ_FD as c_int) {
+                Ok(fd) => fd,
+                Err(e) => {
+                    unsafe { zmq_sys::zmq_close(sock) };
+                    return Err(e);
+                }
+            };
+            // Registered before the context can start terminating, so
+            // that `destroy_with_timeout()` waits for the socket.
+            if let Some(ref registry) = self.raw.registry {
+                registry.register(sock as usize, socket_type, Arc::clone(&endpoints));
+            }
+            Ok((sock, fd))
+        })?;
 
         Ok(Socket {
             sock,
+            fd,
             context: Some(self.clone()),
             owned: true,
+            endpoints,
+            last_error: Cell::new(None),
         })
     }
 
+    /// Create two `PAIR` sockets connected to each other over inproc,
+    /// e.g. for talking to another thread.
+    ///
+    /// See `socket_pair()` for details.
+    pub fn pipe(&self) -> Result<(Socket, Socket)> {
+        self.connected_pair(PAIR, PAIR, None)
+    }
+
+    /// Like `pipe()`, but with the send and receive high-water marks of
+    /// both sockets set to `hwm`.
+    pub fn pipe_with_hwm(&self, hwm: i32) -> Result<(Socket, Socket)> {
+        self.connected_pair(PAIR, PAIR, Some(hwm))
+    }
+
+    /// Create two sockets connected to each other over inproc, e.g. a
+    /// `PUSH` and a `PULL` socket.
+    ///
+    /// The first socket is bound to a generated endpoint, which is
+    /// unique within the process, and the second socket is connected to
+    /// it. Both are ready for use once this returns.
+    pub fn socket_pair(
+        &self,
+        bind_type: SocketType,
+        connect_type: SocketType,
+    ) -> Result<(Socket, Socket)> {
+        self.connected_pair(bind_type, connect_type, None)
+    }
+
+    fn connected_pair(
+        &self,
+        bind_type: SocketType,
+        connect_type: SocketType,
+        hwm: Option<i32>,
+    ) -> Result<(Socket, Socket)> {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let endpoint = format!(
+            "inproc://zmq-pipe-{}",
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        let bound = self.socket(bind_type)?;
+        let connected = self.socket(connect_type)?;
+        if let Some(hwm) = hwm {
+            for socket in &[&bound, &connected] {
+                socket.set_sndhwm(hwm)?;
+                socket.set_rcvhwm(hwm)?;
+            }
+        }
+        bound.bind(endpoint.as_str())?;
+        connected.connect(endpoint.as_str())?;
+        Ok((bound, connected))
+    }
+
     /// Try to destroy the context. This is different than the destructor; the
     /// destructor will loop when zmq_ctx_term returns EINTR.
+    ///
+    /// Once the context has been destroyed, operations on it and its
+    /// clones fail with `Error::ETERM`; destroying it again is a no-op.
     pub fn destroy(&mut self) -> Result<()> {
         self.raw.term()
     }
+
+    /// Destroy the context once all of its sockets have been closed,
+    /// waiting at most `timeout` for that to happen.
+    ///
+    /// This requires the socket registry to be enabled (see
+    /// `ContextBuilder::socket_registry()`), and fails with `EINVAL`
+    /// otherwise. If sockets are still open when the timeout
+    /// expires, the context is left intact, and the information about
+    /// the leaked sockets is returned in the `Err` part of the inner
+    /// result.
+    ///
+    /// While waiting, creating sockets and other uses of the context
+    /// fail with `Error::ETERM`, as they would once it is destroyed.
+    pub fn destroy_with_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<result::Result<(), Vec<SocketInfo>>> {
+        let registry = self.raw.registry.as_ref().ok_or(Error::EINVAL)?;
+        if !self.raw.start_term() {
+            return Ok(Ok(()));
+        }
+        let leaked = registry.wait_closed(timeout);
+        if !leaked.is_empty() {
+            self.raw.terminating.store(false, Ordering::SeqCst);
+            return Ok(Err(leaked));
+        }
+        self.raw.finish_term().map(Ok)
+    }
+
+    /// Return information about all sockets of this context that are
+    /// still open.
+    ///
+    /// This requires the socket registry to be enabled (see
+    /// `ContextBuilder::socket_registry()`); otherwise, `None` is
+    /// returned.
+    pub fn live_sockets(&self) -> Option<Vec<SocketInfo>> {
+        self.raw.registry.as_ref().map(SocketRegistry::live_sockets)
+    }
+
+    /// Shut down the context without waiting for its sockets to be
+    /// closed.
+    ///
+    /// All blocking operations currently in progress on sockets of
+    /// this context return `Error::ETERM`, as will any further
+    /// operation on them except closing, and creating new sockets
+    /// fails. The context itself is terminated once the last socket
+    /// has been dropped. Shutting down a context that has already been
+    /// destroyed is a no-op.
+    pub fn shutdown(&self) -> Result<()> {
+        self.raw.shutdown()
+    }
+
+    /// Create a handle that can shut down the context from another
+    /// thread.
+    ///
+    /// The handle does not keep the context alive; see
+    /// `ShutdownHandle` for details.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            raw: Arc::downgrade(&self.raw),
+        }
+    }
+}
+
+/// A cloneable handle for shutting down a `Context`.
+///
+/// This is intended for a supervisor thread that needs to stop worker
+/// threads blocked in socket operations: calling `shutdown()` makes
+/// those operations return `Error::ETERM`, so the workers can exit
+/// and drop their sockets, which in turn allows the context to be
+/// terminated.
+///
+/// Note that `zmq_ctx_shutdown` is not async-signal-safe, so the
+/// handle must not be used from within a signal handler itself;
+/// instead, forward the signal to a regular thread, e.g. via a
+/// self-pipe, and call `shutdown()` from there.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    raw: Weak<RawContext>,
+}
+
+impl ShutdownHandle {
+    /// Shut down the context, see `Context::shutdown()`.
+    ///
+    /// If the context has already been terminated, this is a no-op.
+    pub fn shutdown(&self) -> Result<()> {
+        match self.raw.upgrade() {
+            Some(raw) => raw.shutdown(),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Default for Context {
@@ -479,22 +956,185 @@ impl Default for Context {
     }
 }
 
+/// Configures a `Context` before it is handed out.
+///
+/// Most context options only take effect when the context's internal
+/// threads are started, which happens when the first socket is
+/// created. The builder applies all options to a fresh context, so
+/// they are guaranteed to be in place by then.
+///
+/// # Examples
+///
+/// ```
+/// let ctx = zmq::Context::builder()
+///     .io_threads(2)
+///     .thread_name_prefix(7)
+///     .build()
+///     .unwrap();
+/// assert_eq!(ctx.get_io_threads().unwrap(), 2);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ContextBuilder {
+    io_threads: Option<i32>,
+    max_sockets: Option<i32>,
+    thread_priority: Option<i32>,
+    thread_sched_policy: Option<i32>,
+    thread_affinity_cpus: Vec<i32>,
+    thread_name_prefix: Option<i32>,
+    max_msgsz: Option<i32>,
+    blocky: Option<bool>,
+    socket_registry: bool,
+}
+
+impl ContextBuilder {
+    /// Create a builder with all options left at their defaults.
+    pub fn new() -> ContextBuilder {
+        ContextBuilder::default()
+    }
+
+    /// See `Context::set_io_threads()`.
+    pub fn io_threads(&mut self, value: i32) -> &mut ContextBuilder {
+        self.io_threads = Some(value);
+        self
+    }
+
+    /// See `Context::set_max_sockets()`.
+    pub fn max_sockets(&mut self, value: i32) -> &mut ContextBuilder {
+        self.max_sockets = Some(value);
+        self
+    }
+
+    /// See `Context::set_thread_priority()`.
+    pub fn thread_priority(&mut self, value: i32) -> &mut ContextBuilder {
+        self.thread_priority = Some(value);
+        self
+    }
+
+    /// See `Context::set_thread_sched_policy()`.
+    pub fn thread_sched_policy(&mut self, value: i32) -> &mut ContextBuilder {
+        self.thread_sched_policy = Some(value);
+        self
+    }
+
+    /// See `Context::add_thread_affinity_cpu()`. May be called
+    /// multiple times to pin the threads to several CPUs.
+    pub fn thread_affinity_cpu(&mut self, cpu: i32) -> &mut ContextBuilder {
+        self.thread_affinity_cpus.push(cpu);
+        self
+    }
+
+    /// See `Context::set_thread_name_prefix()`.
+    pub fn thread_name_prefix(&mut self, value: i32) -> &mut ContextBuilder {
+        self.thread_name_prefix = Some(value);
+        self
+    }
+
+    /// See `Context::set_max_msgsz()`.
+    pub fn max_msgsz(&mut self, value: i32) -> &mut ContextBuilder {
+        self.max_msgsz = Some(value);
+        self
+    }
+
+    /// See `Context::set_blocky()`.
+    pub fn blocky(&mut self, value: bool) -> &mut ContextBuilder {
+        self.blocky = Some(value);
+        self
+    }
+
+    /// Enable the socket registry, which keeps track of all open
+    /// sockets of the context for debugging purposes.
+    ///
+    /// With the registry enabled, `Context::live_sockets()` and
+    /// `Context::destroy_with_timeout()` can be used to find out which
+    /// sockets keep the context from being terminated. Note that this
+    /// captures a backtrace for each socket created, which is
+    /// expensive.
+    pub fn socket_registry(&mut self, enabled: bool) -> &mut ContextBuilder {
+        self.socket_registry = enabled;
+        self
+    }
+
+    /// Create a new context with the configured options applied.
+    pub fn build(&self) -> Result<Context> {
+        let registry = if self.socket_registry {
+            Some(SocketRegistry::default())
+        } else {
+            None
+        };
+        let ctx = Context::with_registry(registry);
+        if let Some(value) = self.io_threads {
+            ctx.set_io_threads(value)?;
+        }
+        if let Some(value) = self.max_sockets {
+            ctx.set_max_sockets(value)?;
+        }
+        if let Some(value) = self.thread_priority {
+            ctx.set_thread_priority(value)?;
+        }
+        if let Some(value) = self.thread_sched_policy {
+            ctx.set_thread_sched_policy(value)?;
+        }
+        for &cpu in &self.thread_affinity_cpus {
+            ctx.add_thread_affinity_cpu(cpu)?;
+        }
+        if let Some(value) = self.thread_name_prefix {
+            ctx.set_thread_name_prefix(value)?;
+        }
+        if let Some(value) = self.max_msgsz {
+            ctx.set_max_msgsz(value)?;
+        }
+        if let Some(value) = self.blocky {
+            ctx.set_blocky(value)?;
+        }
+        Ok(ctx)
+    }
+}
+
 /// A socket, the central object in 0MQ.
 pub struct Socket {
     sock: *mut c_void,
+    // The `ZMQ_FD` descriptor, which stays the same for the lifetime of
+    // the socket. It is queried once, so that `as_fd()` cannot fail.
+    fd: RawFd,
     // The `context` field is never accessed, but implicitly does
     // reference counting via the `Drop` trait.
     #[allow(dead_code)]
     context: Option<Context>,
     owned: bool,
+    endpoints: Arc<Mutex<Endpoints>>,
+    /// `Socket` is not `Sync`, so this is never accessed concurrently.
+    last_error: Cell<Option<(Error, Operation)>>,
 }
 
 unsafe impl Send for Socket {}
 
+/// The endpoints a socket is bound and connected to, as recorded by the
+/// `Socket` methods. The socket registry reads them from here as well.
+#[derive(Default)]
+struct Endpoints {
+    bound: Vec<BoundEndpoint>,
+    connected: Vec<Endpoint>,
+}
+
+/// An endpoint a socket is bound to, as passed to `bind()` and as
+/// resolved by libzmq.
+struct BoundEndpoint {
+    requested: Endpoint,
+    resolved: Endpoint,
+}
+
+impl BoundEndpoint {
+    fn matches(&self, endpoint: &Endpoint) -> bool {
+        self.requested == *endpoint || self.resolved == *endpoint
+    }
+}
+
 impl Drop for Socket {
     fn drop(&mut self) {
-        if self.owned && unsafe { zmq_sys::zmq_close(self.sock) } == -1 {
-            panic!("{}", errno_to_error());
+        if self.owned {
+            if let Err(e) = self.close_inner() {
+                error!("failed to close socket: {}", e);
+            }
         }
     }
 }
@@ -502,14 +1142,32 @@ impl Drop for Socket {
 #[cfg(unix)]
 impl AsRawFd for Socket {
     fn as_raw_fd(&self) -> UnixRawFd {
-        self.get_fd().unwrap() as UnixRawFd
+        self.fd as UnixRawFd
     }
 }
 
 #[cfg(windows)]
 impl AsRawSocket for Socket {
     fn as_raw_socket(&self) -> RawSocket {
-        self.get_fd().unwrap() as RawSocket
+        self.fd as RawSocket
+    }
+}
+
+/// The borrowed file descriptor is the one returned by `get_fd()`, which
+/// is owned by libzmq and stays valid until the socket is closed.
+#[cfg(unix)]
+impl AsFd for Socket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        unsafe { BorrowedFd::borrow_raw(self.as_raw_fd()) }
+    }
+}
+
+/// The borrowed socket is the one returned by `get_fd()`, which is owned
+/// by libzmq and stays valid until the socket is closed.
+#[cfg(windows)]
+impl AsSocket for Socket {
+    fn as_socket(&self) -> BorrowedSocket<'_> {
+        unsafe { BorrowedSocket::borrow_raw(self.as_raw_socket()) }
     }
 }
 
@@ -519,7 +1177,10 @@ macro_rules! sockopt_getter {
     ) => {
         $(#[$meta])*
         pub fn $getter(&self) -> Result<$ty> {
-            <$ty as sockopt::Getter>::get(self.sock, zmq_sys::$constant_name as c_int)
+            self.record(
+                || Operation::GetSockOpt(stringify!($constant_name)),
+                <$ty as sockopt::Getter>::get(self.sock, zmq_sys::$constant_name as c_int),
+            )
         }
     };
 }
@@ -530,7 +1191,10 @@ macro_rules! sockopt_setter {
     ) => {
         $(#[$meta])*
         pub fn $setter(&self, value: $ty) -> Result<()> {
-            <$ty as sockopt::Setter>::set(self.sock, zmq_sys::$constant_name as c_int, value)
+            self.record(
+                || Operation::SetSockOpt(stringify!($constant_name)),
+                <$ty as sockopt::Setter>::set(self.sock, zmq_sys::$constant_name as c_int, value),
+            )
         }
     };
 }
@@ -608,6 +1272,9 @@ impl Socket {
     /// will lead to a memory leak. Also note that is function
     /// relinquishes the reference on the context is was created from.
     pub fn into_raw(mut self) -> *mut c_void {
+        if let Some(registry) = self.registry() {
+            registry.deregister(self.sock as usize);
+        }
         self.owned = false;
         self.sock
     }
@@ -623,11 +1290,21 @@ impl Socket {
     /// method. The ownership of the socket is transferred the returned Socket,
     /// so any other pointers to the same socket may only be used until it is
     /// dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the socket's `ZMQ_FD` option can't be read, which
+    /// happens if its context has been shut down.
     pub unsafe fn from_raw(sock: *mut c_void) -> Socket {
+        let fd = sockopt::get(sock, zmq_sys::ZMQ_FD as c_int)
+            .expect("failed to get the ZMQ_FD option of a raw socket");
         Socket {
             sock,
+            fd,
             context: None,
             owned: true,
+            endpoints: Arc::default(),
+            last_error: Cell::new(None),
         }
     }
 
@@ -640,34 +1317,311 @@ impl Socket {
         self.sock
     }
 
+    /// Close the socket, reporting any error that occurs.
+    ///
+    /// Dropping a socket closes it as well, but errors are only
+    /// logged in that case.
+    pub fn close(mut self) -> Result<()> {
+        self.close_inner()
+    }
+
+    /// Close the socket, bounding the time spent on delivering
+    /// pending outbound messages.
+    ///
+    /// This sets the `ZMQ_LINGER` option to `linger`, rounded to
+    /// milliseconds, before closing the socket. Note that with
+    /// libzmq, the lingering actually happens when the context is
+    /// terminated, not during this call.
+    pub fn close_with_linger(self, linger: Duration) -> Result<()> {
+        let linger_ms = linger.as_millis().min(i32::MAX as u128) as i32;
+        self.set_linger(linger_ms)?;
+        self.close()
+    }
+
+    fn close_inner(&mut self) -> Result<()> {
+        if let Some(registry) = self.registry() {
+            registry.deregister(self.sock as usize);
+        }
+        // The socket must not be closed again by `Drop`, even if
+        // closing fails.
+        self.owned = false;
+        zmq_try!(unsafe { zmq_sys::zmq_close(self.sock) });
+        Ok(())
+    }
+
+    fn registry(&self) -> Option<&SocketRegistry> {
+        self.context
+            .as_ref()
+            .and_then(|ctx| ctx.raw.registry.as_ref())
+    }
+
+    fn endpoints(&self) -> MutexGuard<'_, Endpoints> {
+        // The endpoints are always left in a consistent state, so
+        // recovering from poisoning is fine.
+        self.endpoints.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record that the socket has been used by the current thread.
+    fn touch(&self) {
+        if let Some(registry) = self.registry() {
+            registry.touch(self.sock as usize);
+        }
+    }
+
+    /// Record `operation` as the cause of the error in `result`, if
+    /// any, for `last_error()`.
+    fn record<T>(&self, operation: impl FnOnce() -> Operation, result: Result<T>) -> Result<T> {
+        if let Err(error) = result {
+            self.last_error.set(Some((error, operation())));
+        }
+        result
+    }
+
+    /// Return the last operation that failed on this socket, together
+    /// with its error.
+    ///
+    /// Binding, connecting, sending, receiving and the socket option
+    /// accessors record their failures, so this can describe an error
+    /// after the fact, e.g. for logging. See also
+    /// `ResultExt::with_socket()`.
+    pub fn last_error(&self) -> Option<ErrorContext> {
+        let last_error = self.last_error.take();
+        self.last_error.set(last_error.clone());
+        last_error.map(|(error, operation)| ErrorContext::new(error, self, operation))
+    }
+
     /// Accept connections on a socket.
+    ///
+    /// Endpoints that libzmq would reject anyway, e.g. ones with an
+    /// unknown transport or without a port, are reported as an error
+    /// without calling into libzmq; see `Endpoint` for details.
     pub fn bind(&self, endpoint: &str) -> Result<()> {
-        let c_str = ffi::CString::new(endpoint.as_bytes()).unwrap();
-        zmq_try!(unsafe { zmq_sys::zmq_bind(self.sock, c_str.as_ptr()) });
+        let result = self.bind_inner(endpoint);
+        self.record(|| Operation::Bind(endpoint.into()), result)
+    }
+
+    fn bind_inner(&self, endpoint: &str) -> Result<()> {
+        let endpoint = Endpoint::parse(endpoint)?;
+        zmq_try!(unsafe { zmq_sys::zmq_bind(self.sock, endpoint.as_c_str().as_ptr()) });
+        let resolved = match self.get_last_endpoint() {
+            Ok(Ok(resolved)) => Endpoint::parse(&resolved).unwrap_or_else(|_| endpoint.clone()),
+            _ => endpoint.clone(),
+        };
+        self.endpoints().bound.push(BoundEndpoint {
+            requested: endpoint,
+            resolved,
+        });
+        self.touch();
         Ok(())
     }
 
+    /// Accept connections on an already listening socket `fd`.
+    ///
+    /// This uses the `ZMQ_USE_FD` option, so that libzmq adopts `fd`
+    /// instead of creating a listening socket itself. `endpoint` must
+    /// describe the address `fd` is bound to, e.g. `tcp://*:5555` or
+    /// `ipc:///run/service.sock`. This allows binding to sockets opened
+    /// by a more privileged process, or passed in by systemd socket
+    /// activation, see `listen_fds()`.
+    ///
+    /// The socket takes ownership of `fd`, and closes it when it is
+    /// unbound or closed. `fd` is consumed even if binding fails, as
+    /// libzmq may already have closed it.
+    #[cfg(unix)]
+    pub fn bind_fd(&self, fd: OwnedFd, endpoint: &str) -> Result<()> {
+        let result = self.bind_fd_inner(fd, endpoint);
+        self.record(|| Operation::Bind(endpoint.into()), result)
+    }
+
+    /// Like `bind_fd()`, but for a raw file descriptor, e.g. one
+    /// inherited from a parent process under a known number.
+    ///
+    /// # Safety
+    ///
+    /// `fd` must be an open, listening socket that is not owned by
+    /// anything else; the socket takes ownership of it like with
+    /// `bind_fd()`.
+    #[cfg(unix)]
+    pub unsafe fn bind_raw_fd(&self, fd: UnixRawFd, endpoint: &str) -> Result<()> {
+        self.bind_fd(OwnedFd::from_raw_fd(fd), endpoint)
+    }
+
+    #[cfg(unix)]
+    fn bind_fd_inner(&self, fd: OwnedFd, endpoint: &str) -> Result<()> {
+        Endpoint::parse(endpoint)?;
+        sockopt::set(
+            self.sock,
+            zmq_sys::ZMQ_USE_FD as c_int,
+            fd.as_raw_fd() as c_int,
+        )?;
+        // From here on, libzmq is responsible for closing `fd`.
+        let _ = fd.into_raw_fd();
+        let result = self.bind_inner(endpoint);
+        // The option applies to all subsequent binds, so reset it.
+        let reset = sockopt::set(self.sock, zmq_sys::ZMQ_USE_FD as c_int, -1 as c_int);
+        result.and(reset)
+    }
+
+    /// Bind to an ephemeral TCP port on `host`, and return the address
+    /// the socket is bound to.
+    ///
+    /// `host` may be an IP address, including unbracketed IPv6
+    /// addresses, a host name, an interface name, or `*` for all
+    /// interfaces. Note that the returned address of a socket bound to
+    /// all interfaces is not connectable on every platform; use
+    /// `Endpoint::connectable()` to get an endpoint that is:
+    ///
+    /// ```
+    /// let ctx = zmq::Context::new();
+    /// let server = ctx.socket(zmq::PAIR).unwrap();
+    /// let addr = server.bind_ephemeral("0.0.0.0").unwrap();
+    /// assert!(addr.port() != 0);
+    ///
+    /// let client = ctx.socket(zmq::PAIR).unwrap();
+    /// client.connect(zmq::Endpoint::from(addr).connectable().as_str()).unwrap();
+    /// ```
+    pub fn bind_ephemeral(&self, host: &str) -> Result<SocketAddr> {
+        let endpoint = if host.contains(':') && !host.starts_with('[') {
+            format!("tcp://[{}]:*", host)
+        } else {
+            format!("tcp://{}:*", host)
+        };
+        self.bind(endpoint.as_str())?;
+        self.endpoints()
+            .bound
+            .last()
+            .and_then(|bound| bound.resolved.socket_addr())
+            .ok_or(Error::EINVAL)
+    }
+
+    /// The TCP addresses the socket is bound to, as resolved by libzmq.
+    ///
+    /// Endpoints of other transports, and binds that happened outside
+    /// of this `Socket` (e.g. before `Socket::from_raw()`), are not
+    /// included.
+    pub fn local_endpoints(&self) -> Vec<SocketAddr> {
+        self.endpoints()
+            .bound
+            .iter()
+            .filter(|bound| bound.resolved.transport() == Transport::Tcp)
+            .filter_map(|bound| bound.resolved.socket_addr())
+            .collect()
+    }
+
     /// Stop accepting connections on a socket
     pub fn unbind(&self, endpoint: &str) -> Result<()> {
-        let c_str = ffi::CString::new(endpoint.as_bytes()).unwrap();
-        zmq_try!(unsafe { zmq_sys::zmq_unbind(self.sock, c_str.as_ptr()) });
+        let result = self.unbind_inner(endpoint);
+        self.record(|| Operation::Unbind(endpoint.into()), result)
+    }
+
+    fn unbind_inner(&self, endpoint: &str) -> Result<()> {
+        let endpoint = Endpoint::parse(endpoint)?;
+        zmq_try!(unsafe { zmq_sys::zmq_unbind(self.sock, endpoint.as_c_str().as_ptr()) });
+        {
+            let bound = &mut self.endpoints().bound;
+            if let Some(pos) = bound.iter().position(|bound| bound.matches(&endpoint)) {
+                bound.remove(pos);
+            }
+        }
+        self.touch();
         Ok(())
     }
 
     /// Connect a socket.
+    ///
+    /// Endpoints are checked like for `bind()`.
     pub fn connect(&self, endpoint: &str) -> Result<()> {
-        let c_str = ffi::CString::new(endpoint.as_bytes()).unwrap();
-        zmq_try!(unsafe { zmq_sys::zmq_connect(self.sock, c_str.as_ptr()) });
+        let result = self.connect_inner(endpoint);
+        self.record(|| Operation::Connect(endpoint.into()), result)
+    }
+
+    fn connect_inner(&self, endpoint: &str) -> Result<()> {
+        let endpoint = Endpoint::parse(endpoint)?;
+        zmq_try!(unsafe { zmq_sys::zmq_connect(self.sock, endpoint.as_c_str().as_ptr()) });
+        self.endpoints().connected.push(endpoint);
+        self.touch();
         Ok(())
     }
 
     /// Disconnect a previously connected socket
     pub fn disconnect(&self, endpoint: &str) -> Result<()> {
-        let c_str = ffi::CString::new(endpoint.as_bytes()).unwrap();
-        zmq_try!(unsafe { zmq_sys::zmq_disconnect(self.sock, c_str.as_ptr()) });
+        let result = self.disconnect_inner(endpoint);
+        self.record(|| Operation::Disconnect(endpoint.into()), result)
+    }
+
+    fn disconnect_inner(&self, endpoint: &str) -> Result<()> {
+        let endpoint = Endpoint::parse(endpoint)?;
+        zmq_try!(unsafe { zmq_sys::zmq_disconnect(self.sock, endpoint.as_c_str().as_ptr()) });
+        {
+            let connected = &mut self.endpoints().connected;
+            if let Some(pos) = connected.iter().position(|e| *e == endpoint) {
+                connected.remove(pos);
+            }
+        }
+        self.touch();
         Ok(())
     }
 
+    /// The endpoints the socket is bound to.
+    ///
+    /// libzmq offers no way to list the endpoints of a socket, so these
+    /// are the endpoints recorded by `bind()` and `unbind()` on this
+    /// `Socket`. For wildcard binds such as `tcp://*:*`, the endpoint
+    /// as resolved by libzmq is returned.
+    pub fn bound_endpoints(&self) -> Vec<Endpoint> {
+        self.endpoints()
+            .bound
+            .iter()
+            .map(|bound| bound.resolved.clone())
+            .collect()
+    }
+
+    /// The endpoints the socket is connected to, as recorded by
+    /// `connect()` and `disconnect()` on this `Socket`.
+    pub fn connected_endpoints(&self) -> Vec<Endpoint> {
+        self.endpoints().connected.clone()
+    }
+
+    /// Unbind the socket from all endpoints returned by
+    /// `bound_endpoints()`.
+    ///
+    /// All endpoints are tried, even if unbinding from one of them
+    /// fails; the first error is returned.
+    pub fn unbind_all(&self) -> Result<()> {
+        let mut result = Ok(());
+        for endpoint in self.bound_endpoints() {
+            if let Err(e) = self.unbind(endpoint.as_str()) {
+                if e == Error::ENOENT {
+                    // libzmq no longer knows about the endpoint, e.g.
+                    // because it was unbound through another handle.
+                    self.endpoints()
+                        .bound
+                        .retain(|bound| !bound.matches(&endpoint));
+                }
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+
+    /// Disconnect the socket from all endpoints returned by
+    /// `connected_endpoints()`.
+    ///
+    /// All endpoints are tried, even if disconnecting from one of them
+    /// fails; the first error is returned.
+    pub fn disconnect_all(&self) -> Result<()> {
+        let mut result = Ok(());
+        for endpoint in self.connected_endpoints() {
+            if let Err(e) = self.disconnect(endpoint.as_str()) {
+                if e == Error::ENOENT {
+                    self.endpoints().connected.retain(|c| *c != endpoint);
+                }
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+
     /// Configure the socket for monitoring
     pub fn monitor(&self, monitor_endpoint: &str, events: i32) -> Result<()> {
         let c_str = ffi::CString::new(monitor_endpoint.as_bytes()).unwrap();
@@ -685,7 +1639,21 @@ impl Socket {
     where
         T: Sendable,
     {
-        data.send(self, flags)
+        let result = data.send(self, flags);
+        self.record(|| Operation::SendMessage, result)
+    }
+
+    /// Send `msg`, leaving it untouched if sending fails, e.g. with
+    /// `EAGAIN`.
+    #[cfg(all(unix, any(feature = "tokio", feature = "async-io")))]
+    pub(crate) fn send_in_place(&self, msg: &mut Message, flags: i32) -> Result<()> {
+        let rc = unsafe { zmq_sys::zmq_msg_send(msg_ptr(msg), self.sock, flags as c_int) };
+        let result = if rc == -1 {
+            Err(errno_to_error())
+        } else {
+            Ok(())
+        };
+        self.record(|| Operation::SendMessage, result)
     }
 
     /// Send a `Message` message.
@@ -722,8 +1690,13 @@ impl Socket {
     /// Receive a message into a `Message`. The length passed to zmq_msg_recv
     /// is the length of the buffer.
     pub fn recv(&self, msg: &mut Message, flags: i32) -> Result<()> {
-        zmq_try!(unsafe { zmq_sys::zmq_msg_recv(msg_ptr(msg), self.sock, flags as c_int) });
-        Ok(())
+        let rc = unsafe { zmq_sys::zmq_msg_recv(msg_ptr(msg), self.sock, flags as c_int) };
+        let result = if rc == -1 {
+            Err(errno_to_error())
+        } else {
+            Ok(())
+        };
+        self.record(|| Operation::RecvMessage, result)
     }
 
     /// Receive bytes into a slice. The length passed to `zmq_recv` is the length of the slice. The
@@ -731,10 +1704,13 @@ impl Socket {
     /// the slice, indicating truncation.
     pub fn recv_into(&self, bytes: &mut [u8], flags: i32) -> Result<usize> {
         let bytes_ptr = bytes.as_mut_ptr() as *mut c_void;
-        let rc = zmq_try!(unsafe {
-            zmq_sys::zmq_recv(self.sock, bytes_ptr, bytes.len(), flags as c_int)
-        });
-        Ok(rc as usize)
+        let rc = unsafe { zmq_sys::zmq_recv(self.sock, bytes_ptr, bytes.len(), flags as c_int) };
+        let result = if rc == -1 {
+            Err(errno_to_error())
+        } else {
+            Ok(rc as usize)
+        };
+        self.record(|| Operation::RecvMessage, result)
     }
 
     /// Receive a message into a fresh `Message`.
@@ -796,13 +1772,28 @@ impl Socket {
     }
 
     /// Return the type of this socket.
+    ///
+    /// Fails with `EINVAL` if the socket is of a type not known to
+    /// this binding, which can happen for sockets obtained via
+    /// `Socket::from_raw()`.
     pub fn get_socket_type(&self) -> Result<SocketType> {
-        sockopt::get(self.sock, zmq_sys::ZMQ_TYPE as c_int).map(SocketType::from_raw)
+        self.record(
+            || Operation::GetSockOpt("ZMQ_TYPE"),
+            self.socket_type_unrecorded(),
+        )
+    }
+
+    /// Like `get_socket_type()`, but leaving `last_error()` alone.
+    fn socket_type_unrecorded(&self) -> Result<SocketType> {
+        sockopt::get::<i32>(self.sock, zmq_sys::ZMQ_TYPE as c_int).and_then(SocketType::try_from)
     }
 
     /// Return true if there are more frames of a multipart message to receive.
     pub fn get_rcvmore(&self) -> Result<bool> {
-        sockopt::get(self.sock, zmq_sys::ZMQ_RCVMORE as c_int).map(|o: i64| o == 1i64)
+        self.record(
+            || Operation::GetSockOpt("ZMQ_RCVMORE"),
+            sockopt::get(self.sock, zmq_sys::ZMQ_RCVMORE as c_int).map(|o: i64| o == 1i64),
+        )
     }
 
     sockopts! {
@@ -884,38 +1875,55 @@ impl Socket {
     // TODO: deprecate to align with ZMQ's preferred naming
     pub fn get_identity(&self) -> Result<Vec<u8>> {
         // 255 = identity max length
-        sockopt::get_bytes(self.sock, zmq_sys::ZMQ_ROUTING_ID as c_int, 255)
+        self.record(
+            || Operation::GetSockOpt("ZMQ_ROUTING_ID"),
+            sockopt::get_bytes(self.sock, zmq_sys::ZMQ_ROUTING_ID as c_int, 255),
+        )
     }
 
     pub fn get_socks_proxy(&self) -> Result<result::Result<String, Vec<u8>>> {
         // 255 = longest allowable domain name is 253 so this should
         // be a reasonable size.
-        sockopt::get_string(self.sock, zmq_sys::ZMQ_SOCKS_PROXY as c_int, 255, true)
+        self.record(
+            || Operation::GetSockOpt("ZMQ_SOCKS_PROXY"),
+            sockopt::get_string(self.sock, zmq_sys::ZMQ_SOCKS_PROXY as c_int, 255, true),
+        )
     }
 
+    /// Return the security mechanism of this socket.
+    ///
+    /// Fails with `EINVAL` if libzmq reports a mechanism not known to
+    /// this binding.
     pub fn get_mechanism(&self) -> Result<Mechanism> {
-        sockopt::get(self.sock, zmq_sys::ZMQ_MECHANISM as c_int).map(|mech| match mech {
-            zmq_sys::ZMQ_NULL => Mechanism::ZMQ_NULL,
-            zmq_sys::ZMQ_PLAIN => Mechanism::ZMQ_PLAIN,
-            zmq_sys::ZMQ_CURVE => Mechanism::ZMQ_CURVE,
-            zmq_sys::ZMQ_GSSAPI => Mechanism::ZMQ_GSSAPI,
-            _ => panic!("Mechanism is out of range!"),
-        })
+        self.record(
+            || Operation::GetSockOpt("ZMQ_MECHANISM"),
+            sockopt::get::<i32>(self.sock, zmq_sys::ZMQ_MECHANISM as c_int)
+                .and_then(Mechanism::try_from),
+        )
     }
 
     pub fn get_plain_username(&self) -> Result<result::Result<String, Vec<u8>>> {
         // 255 = arbitrary size
-        sockopt::get_string(self.sock, zmq_sys::ZMQ_PLAIN_USERNAME as c_int, 255, true)
+        self.record(
+            || Operation::GetSockOpt("ZMQ_PLAIN_USERNAME"),
+            sockopt::get_string(self.sock, zmq_sys::ZMQ_PLAIN_USERNAME as c_int, 255, true),
+        )
     }
 
     pub fn get_plain_password(&self) -> Result<result::Result<String, Vec<u8>>> {
         // 256 = arbitrary size based on std crypto key size
-        sockopt::get_string(self.sock, zmq_sys::ZMQ_PLAIN_PASSWORD as c_int, 256, true)
+        self.record(
+            || Operation::GetSockOpt("ZMQ_PLAIN_PASSWORD"),
+            sockopt::get_string(self.sock, zmq_sys::ZMQ_PLAIN_PASSWORD as c_int, 256, true),
+        )
     }
 
     pub fn get_zap_domain(&self) -> Result<result::Result<String, Vec<u8>>> {
         // 255 = arbitrary size
-        sockopt::get_string(self.sock, zmq_sys::ZMQ_ZAP_DOMAIN as c_int, 255, true)
+        self.record(
+            || Operation::GetSockOpt("ZMQ_ZAP_DOMAIN"),
+            sockopt::get_string(self.sock, zmq_sys::ZMQ_ZAP_DOMAIN as c_int, 255, true),
+        )
     }
 
     /// Return the address of the last endpoint this socket was bound to.
@@ -928,11 +1936,132 @@ impl Socket {
     /// (i.e. `0.0.0.0` with IPv4).
     pub fn get_last_endpoint(&self) -> Result<result::Result<String, Vec<u8>>> {
         // 256 + 9 + 1 = maximum inproc name size (= 256) + "inproc://".len() (= 9), plus null byte
-        sockopt::get_string(
-            self.sock,
-            zmq_sys::ZMQ_LAST_ENDPOINT as c_int,
-            256 + 9 + 1,
-            true,
+        self.record(
+            || Operation::GetSockOpt("ZMQ_LAST_ENDPOINT"),
+            sockopt::get_string(
+                self.sock,
+                zmq_sys::ZMQ_LAST_ENDPOINT as c_int,
+                256 + 9 + 1,
+                true,
+            ),
+        )
+    }
+
+    /// Only accept `tcp://` connections from peers within `filter`, an
+    /// IP address with an optional prefix length, e.g. `10.0.0.0/8`.
+    ///
+    /// Filters accumulate: once any filter has been added, connections
+    /// from addresses matching none of them are dropped. Filters only
+    /// affect subsequent `bind()` calls. Fails with `EINVAL` if `filter`
+    /// is invalid; IPv6 filters also require `set_ipv6(true)`.
+    ///
+    /// Like the IPC filters, this uses an option libzmq has deprecated
+    /// in favour of ZAP authentication, but which all 4.x releases of
+    /// libzmq still support. The filters are deliberately not
+    /// implemented through ZAP: a context has a single ZAP handler at
+    /// `inproc://zeromq.zap.01`, and installing one here would take it
+    /// away from the application's own handler, e.g. for CURVE.
+    /// Applications running a ZAP handler can check the peer address
+    /// there instead; if a libzmq release removes the options, these
+    /// methods fail with `EINVAL`.
+    pub fn add_tcp_accept_filter(&self, filter: &str) -> Result<()> {
+        self.record(
+            || Operation::SetSockOpt("ZMQ_TCP_ACCEPT_FILTER"),
+            sockopt::set(self.sock, zmq_sys::ZMQ_TCP_ACCEPT_FILTER as c_int, filter),
+        )
+    }
+
+    /// Remove all filters added with `add_tcp_accept_filter()`.
+    pub fn clear_tcp_accept_filters(&self) -> Result<()> {
+        self.record(
+            || Operation::SetSockOpt("ZMQ_TCP_ACCEPT_FILTER"),
+            sockopt::set(
+                self.sock,
+                zmq_sys::ZMQ_TCP_ACCEPT_FILTER as c_int,
+                None::<&str>,
+            ),
+        )
+    }
+
+    /// Only accept `ipc://` connections from processes running as the
+    /// user `uid`.
+    ///
+    /// Filters accumulate: a connection is accepted if it matches any
+    /// UID, GID or PID filter. Filters only affect subsequent `bind()`
+    /// calls. This fails with `EINVAL` if libzmq was built without
+    /// support for determining the credentials of IPC peers, which
+    /// requires `SO_PEERCRED` or `LOCAL_PEERCRED`. See
+    /// `add_tcp_accept_filter()` for why this does not use ZAP.
+    #[cfg(unix)]
+    pub fn add_ipc_uid_filter(&self, uid: libc::uid_t) -> Result<()> {
+        self.record(
+            || Operation::SetSockOpt("ZMQ_IPC_FILTER_UID"),
+            sockopt::set(self.sock, zmq_sys::ZMQ_IPC_FILTER_UID as c_int, uid),
+        )
+    }
+
+    /// Remove all filters added with `add_ipc_uid_filter()`.
+    #[cfg(unix)]
+    pub fn clear_ipc_uid_filters(&self) -> Result<()> {
+        self.record(
+            || Operation::SetSockOpt("ZMQ_IPC_FILTER_UID"),
+            sockopt::set(
+                self.sock,
+                zmq_sys::ZMQ_IPC_FILTER_UID as c_int,
+                None::<&str>,
+            ),
+        )
+    }
+
+    /// Only accept `ipc://` connections from processes running with the
+    /// primary group `gid`, or with a user that is a member of it.
+    ///
+    /// See `add_ipc_uid_filter()` for how filters are combined.
+    #[cfg(unix)]
+    pub fn add_ipc_gid_filter(&self, gid: libc::gid_t) -> Result<()> {
+        self.record(
+            || Operation::SetSockOpt("ZMQ_IPC_FILTER_GID"),
+            sockopt::set(self.sock, zmq_sys::ZMQ_IPC_FILTER_GID as c_int, gid),
+        )
+    }
+
+    /// Remove all filters added with `add_ipc_gid_filter()`.
+    #[cfg(unix)]
+    pub fn clear_ipc_gid_filters(&self) -> Result<()> {
+        self.record(
+            || Operation::SetSockOpt("ZMQ_IPC_FILTER_GID"),
+            sockopt::set(
+                self.sock,
+                zmq_sys::ZMQ_IPC_FILTER_GID as c_int,
+                None::<&str>,
+            ),
+        )
+    }
+
+    /// Only accept `ipc://` connections from the process `pid`.
+    ///
+    /// See `add_ipc_uid_filter()` for how filters are combined. PID
+    /// filters are only supported on Linux, and only if libzmq was
+    /// built with `SO_PEERCRED` support; otherwise this fails with
+    /// `EINVAL`.
+    #[cfg(unix)]
+    pub fn add_ipc_pid_filter(&self, pid: libc::pid_t) -> Result<()> {
+        self.record(
+            || Operation::SetSockOpt("ZMQ_IPC_FILTER_PID"),
+            sockopt::set(self.sock, zmq_sys::ZMQ_IPC_FILTER_PID as c_int, pid),
+        )
+    }
+
+    /// Remove all filters added with `add_ipc_pid_filter()`.
+    #[cfg(unix)]
+    pub fn clear_ipc_pid_filters(&self) -> Result<()> {
+        self.record(
+            || Operation::SetSockOpt("ZMQ_IPC_FILTER_PID"),
+            sockopt::set(
+                self.sock,
+                zmq_sys::ZMQ_IPC_FILTER_PID as c_int,
+                None::<&str>,
+            ),
         )
     }
 
@@ -942,7 +2071,10 @@ impl Socket {
     /// resulting data to get the Z85-encoded string representation of
     /// the key.
     pub fn get_curve_publickey(&self) -> Result<Vec<u8>> {
-        sockopt::get_bytes(self.sock, zmq_sys::ZMQ_CURVE_PUBLICKEY as c_int, 32)
+        self.record(
+            || Operation::GetSockOpt("ZMQ_CURVE_PUBLICKEY"),
+            sockopt::get_bytes(self.sock, zmq_sys::ZMQ_CURVE_PUBLICKEY as c_int, 32),
+        )
     }
 
     /// Get the `ZMQ_CURVE_SECRETKEY` option value.
@@ -951,7 +2083,10 @@ impl Socket {
     /// resulting data to get the Z85-encoded string representation of
     /// the key.
     pub fn get_curve_secretkey(&self) -> Result<Vec<u8>> {
-        sockopt::get_bytes(self.sock, zmq_sys::ZMQ_CURVE_SECRETKEY as c_int, 32)
+        self.record(
+            || Operation::GetSockOpt("ZMQ_CURVE_SECRETKEY"),
+            sockopt::get_bytes(self.sock, zmq_sys::ZMQ_CURVE_SECRETKEY as c_int, 32),
+        )
     }
 
     /// Get `ZMQ_CURVE_SERVERKEY` option value.
@@ -961,21 +2096,30 @@ impl Socket {
     /// Z85-encoded string variant.
     pub fn get_curve_serverkey(&self) -> Result<Vec<u8>> {
         // 41 = Z85 encoded keysize + 1 for null byte
-        sockopt::get_bytes(self.sock, zmq_sys::ZMQ_CURVE_SERVERKEY as c_int, 32)
+        self.record(
+            || Operation::GetSockOpt("ZMQ_CURVE_SERVERKEY"),
+            sockopt::get_bytes(self.sock, zmq_sys::ZMQ_CURVE_SERVERKEY as c_int, 32),
+        )
     }
 
     pub fn get_gssapi_principal(&self) -> Result<result::Result<String, Vec<u8>>> {
         // 260 = best guess of max length based on docs.
-        sockopt::get_string(self.sock, zmq_sys::ZMQ_GSSAPI_PRINCIPAL as c_int, 260, true)
+        self.record(
+            || Operation::GetSockOpt("ZMQ_GSSAPI_PRINCIPAL"),
+            sockopt::get_string(self.sock, zmq_sys::ZMQ_GSSAPI_PRINCIPAL as c_int, 260, true),
+        )
     }
 
     pub fn get_gssapi_service_principal(&self) -> Result<result::Result<String, Vec<u8>>> {
         // 260 = best guess of max length based on docs.
-        sockopt::get_string(
-            self.sock,
-            zmq_sys::ZMQ_GSSAPI_SERVICE_PRINCIPAL as c_int,
-            260,
-            true,
+        self.record(
+            || Operation::GetSockOpt("ZMQ_GSSAPI_SERVICE_PRINCIPAL"),
+            sockopt::get_string(
+                self.sock,
+                zmq_sys::ZMQ_GSSAPI_SERVICE_PRINCIPAL as c_int,
+                260,
+                true,
+            ),
         )
     }
 
@@ -995,7 +2139,7 @@ impl Socket {
     }
 
     /// Create a `PollItem` from the socket.
-    pub fn as_poll_item(&self, events: PollEvents) -> PollItem {
+    pub fn as_poll_item(&self, events: PollEvents) -> PollItem<'_> {
         PollItem {
             socket: self.sock,
             fd: 0,
@@ -1061,6 +2205,14 @@ pub struct PollItem<'a> {
 impl<'a> PollItem<'a> {
     /// Construct a PollItem from a non-0MQ socket, given by its file
     /// descriptor and the events that should be polled.
+    ///
+    /// Nothing ensures that `fd` stays open while the `PollItem` is
+    /// in use; prefer `from_borrowed_fd()` (or `from_borrowed_socket()`
+    /// on Windows), which does.
+    #[deprecated(
+        since = "0.11.0",
+        note = "Use `from_borrowed_fd` or `from_borrowed_socket` instead"
+    )]
     pub fn from_fd(fd: RawFd, events: PollEvents) -> PollItem<'a> {
         PollItem {
             socket: ptr::null_mut(),
@@ -1071,6 +2223,33 @@ impl<'a> PollItem<'a> {
         }
     }
 
+    /// Construct a PollItem from a non-0MQ socket, given by a file
+    /// descriptor borrowed for the lifetime of the `PollItem`, and the
+    /// events that should be polled.
+    #[cfg(unix)]
+    pub fn from_borrowed_fd(fd: BorrowedFd<'a>, events: PollEvents) -> PollItem<'a> {
+        PollItem {
+            socket: ptr::null_mut(),
+            fd: fd.as_raw_fd(),
+            events: events.bits(),
+            revents: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Construct a PollItem from a non-0MQ socket, borrowed for the
+    /// lifetime of the `PollItem`, and the events that should be polled.
+    #[cfg(windows)]
+    pub fn from_borrowed_socket(socket: BorrowedSocket<'a>, events: PollEvents) -> PollItem<'a> {
+        PollItem {
+            socket: ptr::null_mut(),
+            fd: socket.as_raw_socket(),
+            events: events.bits(),
+            revents: 0,
+            marker: PhantomData,
+        }
+    }
+
     /// Change the events to wait for.
     pub fn set_events(&mut self, events: PollEvents) {
         self.events = events.bits();
@@ -1136,7 +2315,9 @@ pub fn poll(items: &mut [PollItem], timeout: i64) -> Result<i32> {
 /// behavior depends on the type of both sockets.
 ///
 /// This function only returns (always with an `Err`) when the sockets' context
-/// has been closed.
+/// has been closed. See `spawn_proxy` for running a proxy on a background
+/// thread that can be stopped, and `Proxy` for a proxy that allows inspecting
+/// and filtering messages.
 pub fn proxy(frontend: &Socket, backend: &Socket) -> Result<()> {
     zmq_try!(unsafe { zmq_sys::zmq_proxy(frontend.sock, backend.sock, ptr::null_mut()) });
     Ok(())
@@ -1160,6 +2341,9 @@ pub fn proxy_with_capture(
 /// If PAUSE is received on the control socket, the proxy suspends its activities. If RESUME is received,
 /// it goes on. If TERMINATE is received, it terminates smoothly. At start, the proxy runs normally
 /// as if `proxy` was used.
+///
+/// See `spawn_proxy_steerable` for running the proxy on a background thread,
+/// steered through a typed `ProxyController`.
 pub fn proxy_steerable(
     frontend: &mut Socket,
     backend: &mut Socket,
@@ -0,0 +1,317 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use log::warn;
+
+use super::{
+    CLIENT, C_FINAL, C_PARTIAL, C_REQUEST, HEARTBEAT_INTERVAL, WORKER, W_DISCONNECT, W_FINAL,
+    W_HEARTBEAT, W_PARTIAL, W_READY, W_REQUEST,
+};
+use crate::reliable::{millis, HEARTBEAT_LIVENESS};
+use crate::{Context, Result, Socket, POLLIN, ROUTER};
+
+/// A service, with its queue of pending requests and idle workers.
+#[derive(Default)]
+struct Service {
+    /// Requests, each as the client's identity followed by the body.
+    requests: VecDeque<Vec<Vec<u8>>>,
+    /// Identities of idle workers, in the order they became idle.
+    waiting: VecDeque<Vec<u8>>,
+    /// Number of registered workers, idle or not.
+    workers: usize,
+}
+
+/// A worker registered with the broker.
+struct WorkerState {
+    service: Vec<u8>,
+    idle: bool,
+    /// The request the worker is handling, if it is busy.
+    request: Option<Vec<Vec<u8>>>,
+    expiry: Instant,
+}
+
+/// A Majordomo broker, routing client requests to workers by service
+/// name.
+///
+/// Workers that are not heard from for too long are forgotten, and the
+/// request they were handling, if any, is queued again for another
+/// worker; busy workers should therefore send heartbeats, see
+/// `Worker::heartbeat()`. Requests for a service without idle workers
+/// are queued until a worker becomes available.
+pub struct Broker {
+    socket: Socket,
+    services: HashMap<Vec<u8>, Service>,
+    workers: HashMap<Vec<u8>, WorkerState>,
+    heartbeat_interval: Duration,
+    heartbeat_liveness: u32,
+}
+
+impl Broker {
+    /// Create a broker with a new `ROUTER` socket.
+    pub fn new(ctx: &Context) -> Result<Broker> {
+        Ok(Broker {
+            socket: ctx.socket(ROUTER)?,
+            services: HashMap::new(),
+            workers: HashMap::new(),
+            heartbeat_interval: HEARTBEAT_INTERVAL,
+            heartbeat_liveness: HEARTBEAT_LIVENESS,
+        })
+    }
+
+    /// Accept clients and workers on `endpoint`, see `Socket::bind()`.
+    pub fn bind(&self, endpoint: &str) -> Result<()> {
+        self.socket.bind(endpoint)
+    }
+
+    /// Set the interval between heartbeats sent to idle workers, which
+    /// must match the workers' setting. Defaults to 2.5 seconds.
+    pub fn set_heartbeat_interval(&mut self, interval: Duration) {
+        self.heartbeat_interval = interval;
+    }
+
+    /// Set the number of heartbeat intervals after which a silent
+    /// worker is forgotten. Defaults to 3.
+    pub fn set_heartbeat_liveness(&mut self, liveness: u32) {
+        self.heartbeat_liveness = liveness;
+    }
+
+    /// Get a reference to the broker's socket.
+    pub fn get_ref(&self) -> &Socket {
+        &self.socket
+    }
+
+    /// Run the broker in the current thread.
+    ///
+    /// This only returns (always with an `Err`) when an operation on the
+    /// socket fails, typically with `ETERM` because the context has been
+    /// shut down.
+    pub fn run(&mut self) -> Result<()> {
+        let mut heartbeat_at = Instant::now() + self.heartbeat_interval;
+        loop {
+            let timeout = heartbeat_at.saturating_duration_since(Instant::now());
+            if self.socket.poll(POLLIN, millis(timeout))? > 0 {
+                let frames = self.socket.recv_multipart(0)?;
+                self.handle(frames)?;
+            }
+            if Instant::now() >= heartbeat_at {
+                self.purge()?;
+                self.heartbeat()?;
+                heartbeat_at = Instant::now() + self.heartbeat_interval;
+            }
+        }
+    }
+
+    fn handle(&mut self, mut frames: Vec<Vec<u8>>) -> Result<()> {
+        if frames.len() < 3 {
+            warn!("mdp broker: ignoring invalid message");
+            return Ok(());
+        }
+        let mut rest = frames.split_off(3);
+        let command = frames.pop().unwrap();
+        let header = frames.pop().unwrap();
+        let sender = frames.pop().unwrap();
+        if header == CLIENT.as_bytes() && command == C_REQUEST && !rest.is_empty() {
+            let service = rest.remove(0);
+            self.handle_client(sender, service, rest)
+        } else if header == WORKER.as_bytes() {
+            self.handle_worker(sender, &command, rest)
+        } else {
+            warn!("mdp broker: ignoring invalid message");
+            Ok(())
+        }
+    }
+
+    fn handle_client(
+        &mut self,
+        client: Vec<u8>,
+        service: Vec<u8>,
+        body: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        if service.starts_with(b"mmi.") {
+            return self.handle_mmi(client, service, body);
+        }
+        let mut request = body;
+        request.insert(0, client);
+        self.services
+            .entry(service.clone())
+            .or_default()
+            .requests
+            .push_back(request);
+        self.dispatch(&service)
+    }
+
+    /// Answer a request for the Majordomo Management Interface.
+    fn handle_mmi(&self, client: Vec<u8>, service: Vec<u8>, body: Vec<Vec<u8>>) -> Result<()> {
+        let code: &[u8] = if service == b"mmi.service" {
+            let known = body
+                .first()
+                .and_then(|name| self.services.get(name))
+                .is_some_and(|service| service.workers > 0);
+            if known {
+                b"200"
+            } else {
+                b"404"
+            }
+        } else {
+            b"501"
+        };
+        self.socket
+            .send_multipart([&client[..], CLIENT.as_bytes(), C_FINAL, &service, code], 0)
+    }
+
+    fn handle_worker(&mut self, sender: Vec<u8>, command: &[u8], body: Vec<Vec<u8>>) -> Result<()> {
+        let expiry = Instant::now() + self.expiry();
+        let known = match self.workers.get_mut(&sender) {
+            Some(worker) => {
+                worker.expiry = expiry;
+                true
+            }
+            None => false,
+        };
+        match command {
+            W_READY if known => {
+                // A worker must not register twice.
+                self.delete_worker(&sender, true)
+            }
+            W_READY => match body.first() {
+                Some(service) if !service.starts_with(b"mmi.") => {
+                    self.services.entry(service.clone()).or_default().workers += 1;
+                    let worker = WorkerState {
+                        service: service.clone(),
+                        idle: false,
+                        request: None,
+                        expiry,
+                    };
+                    self.workers.insert(sender.clone(), worker);
+                    self.worker_waiting(sender)
+                }
+                _ => self.disconnect(&sender),
+            },
+            W_PARTIAL | W_FINAL if known && body.len() >= 2 => {
+                let service = self.workers[&sender].service.clone();
+                let reply = if command == W_FINAL {
+                    C_FINAL
+                } else {
+                    C_PARTIAL
+                };
+                let client = &body[0];
+                let header = [&client[..], CLIENT.as_bytes(), reply, &service];
+                let frames = header
+                    .iter()
+                    .copied()
+                    .chain(body[2..].iter().map(Vec::as_slice));
+                self.socket.send_multipart(frames, 0)?;
+                if command == W_FINAL {
+                    self.worker_waiting(sender)?;
+                }
+                Ok(())
+            }
+            W_HEARTBEAT if known => Ok(()),
+            W_DISCONNECT => {
+                if known {
+                    self.delete_worker(&sender, false)?;
+                }
+                Ok(())
+            }
+            _ if known => self.delete_worker(&sender, true),
+            _ => self.disconnect(&sender),
+        }
+    }
+
+    /// Mark a worker as idle, and give it a request if one is pending.
+    fn worker_waiting(&mut self, identity: Vec<u8>) -> Result<()> {
+        let worker = self.workers.get_mut(&identity).unwrap();
+        worker.idle = true;
+        worker.request = None;
+        let service = worker.service.clone();
+        self.services
+            .get_mut(&service)
+            .unwrap()
+            .waiting
+            .push_back(identity);
+        self.dispatch(&service)
+    }
+
+    /// Pass pending requests for `service` to its idle workers.
+    fn dispatch(&mut self, service: &[u8]) -> Result<()> {
+        let service = match self.services.get_mut(service) {
+            Some(service) => service,
+            None => return Ok(()),
+        };
+        while !service.waiting.is_empty() && !service.requests.is_empty() {
+            let identity = service.waiting.pop_front().unwrap();
+            let request = service.requests.pop_front().unwrap();
+            let header = [
+                &identity[..],
+                WORKER.as_bytes(),
+                W_REQUEST,
+                &request[0],
+                b"",
+            ];
+            let frames = header
+                .iter()
+                .copied()
+                .chain(request[1..].iter().map(Vec::as_slice));
+            self.socket.send_multipart(frames, 0)?;
+            let worker = self.workers.get_mut(&identity).unwrap();
+            worker.idle = false;
+            worker.request = Some(request);
+        }
+        Ok(())
+    }
+
+    /// Forget a worker, optionally telling it to disconnect.
+    ///
+    /// The request the worker was handling, if any, goes back to the
+    /// front of the queue.
+    fn delete_worker(&mut self, identity: &[u8], disconnect: bool) -> Result<()> {
+        if disconnect {
+            self.disconnect(identity)?;
+        }
+        if let Some(worker) = self.workers.remove(identity) {
+            let service = self.services.get_mut(&worker.service).unwrap();
+            service.workers -= 1;
+            service.waiting.retain(|waiting| waiting[..] != *identity);
+            if let Some(request) = worker.request {
+                service.requests.push_front(request);
+                self.dispatch(&worker.service)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn disconnect(&self, identity: &[u8]) -> Result<()> {
+        self.socket
+            .send_multipart([identity, WORKER.as_bytes(), W_DISCONNECT], 0)
+    }
+
+    /// Forget workers that have not been heard from for too long.
+    fn purge(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let expired: Vec<Vec<u8>> = self
+            .workers
+            .iter()
+            .filter(|(_, worker)| worker.expiry < now)
+            .map(|(identity, _)| identity.clone())
+            .collect();
+        for identity in expired {
+            self.delete_worker(&identity, false)?;
+        }
+        Ok(())
+    }
+
+    /// Send a heartbeat to all idle workers.
+    fn heartbeat(&self) -> Result<()> {
+        for (identity, worker) in &self.workers {
+            if worker.idle {
+                self.socket
+                    .send_multipart([&identity[..], WORKER.as_bytes(), W_HEARTBEAT], 0)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn expiry(&self) -> Duration {
+        self.heartbeat_interval * self.heartbeat_liveness
+    }
+}
@@ -0,0 +1,185 @@
+use std::time::Duration;
+
+use super::{CLIENT, C_FINAL, C_PARTIAL, C_REQUEST};
+use crate::reliable::millis;
+use crate::{Context, Error, Result, Socket, DEALER, POLLIN};
+
+/// The default time to wait for a reply.
+const TIMEOUT: Duration = Duration::from_millis(2500);
+/// The default number of attempts at getting a reply.
+const RETRIES: u32 = 3;
+
+/// A reply received by a `PipelinedClient`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reply {
+    service: Vec<u8>,
+    body: Vec<Vec<u8>>,
+    is_final: bool,
+}
+
+impl Reply {
+    /// The service that sent the reply.
+    pub fn service(&self) -> &[u8] {
+        &self.service
+    }
+
+    /// The frames of the reply body.
+    pub fn body(&self) -> &[Vec<u8>] {
+        &self.body
+    }
+
+    /// Return the frames of the reply body.
+    pub fn into_body(self) -> Vec<Vec<u8>> {
+        self.body
+    }
+
+    /// Return true if this is the final reply to a request, and false if
+    /// it is a partial reply that will be followed by more.
+    pub fn is_final(&self) -> bool {
+        self.is_final
+    }
+}
+
+/// A synchronous Majordomo client, sending one request at a time.
+///
+/// If no reply arrives within the timeout, the client reconnects to
+/// the broker and sends the request again, up to the configured number
+/// of attempts. Requests must therefore be idempotent.
+pub struct Client {
+    ctx: Context,
+    broker: String,
+    socket: Socket,
+    timeout: Duration,
+    retries: u32,
+}
+
+impl Client {
+    /// Connect to the broker at `broker`.
+    pub fn new(ctx: &Context, broker: &str) -> Result<Client> {
+        let broker = broker.to_owned();
+        let socket = connect(ctx, &broker)?;
+        Ok(Client {
+            ctx: ctx.clone(),
+            broker,
+            socket,
+            timeout: TIMEOUT,
+            retries: RETRIES,
+        })
+    }
+
+    /// Set the time to wait for each reply. Defaults to 2.5 seconds.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Set the number of attempts at getting a reply. Defaults to 3.
+    pub fn set_retries(&mut self, retries: u32) {
+        self.retries = retries;
+    }
+
+    /// Send a request to `service`, and wait for the final reply.
+    ///
+    /// Partial replies are skipped; use `PipelinedClient` to receive them.
+    /// Fails with `ETIMEDOUT` if no reply arrives after the configured
+    /// number of attempts, and with `EPROTO` if the broker's reply is
+    /// invalid.
+    pub fn send<T: AsRef<[u8]>>(&mut self, service: &str, request: &[T]) -> Result<Vec<Vec<u8>>> {
+        for _ in 0..self.retries {
+            send_request(&self.socket, service, request)?;
+            while self.socket.poll(POLLIN, millis(self.timeout))? > 0 {
+                let reply = recv_reply(&self.socket)?;
+                if reply.service != service.as_bytes() {
+                    return Err(Error::EPROTO);
+                }
+                if reply.is_final {
+                    return Ok(reply.body);
+                }
+            }
+            // Replies to the abandoned request must not be mistaken for
+            // replies to the next one.
+            self.socket = connect(&self.ctx, &self.broker)?;
+        }
+        Err(Error::ETIMEDOUT)
+    }
+}
+
+/// A Majordomo client which can have any number of requests in flight,
+/// and receives their replies, partial or final, as they arrive.
+///
+/// Unlike `Client`, this does not retry requests.
+pub struct PipelinedClient {
+    socket: Socket,
+    timeout: Duration,
+}
+
+impl PipelinedClient {
+    /// Connect to the broker at `broker`.
+    pub fn new(ctx: &Context, broker: &str) -> Result<PipelinedClient> {
+        let broker = broker.to_owned();
+        Ok(PipelinedClient {
+            socket: connect(ctx, &broker)?,
+            timeout: TIMEOUT,
+        })
+    }
+
+    /// Set the time `recv()` waits for a reply. Defaults to 2.5 seconds.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Get a reference to the client's socket, e.g. for polling it.
+    pub fn get_ref(&self) -> &Socket {
+        &self.socket
+    }
+
+    /// Send a request to `service`, without waiting for a reply.
+    pub fn send<T: AsRef<[u8]>>(&self, service: &str, request: &[T]) -> Result<()> {
+        send_request(&self.socket, service, request)
+    }
+
+    /// Wait for the next reply.
+    ///
+    /// Fails with `ETIMEDOUT` if no reply arrives within the timeout, and
+    /// with `EPROTO` if the broker's reply is invalid.
+    pub fn recv(&self) -> Result<Reply> {
+        if self.socket.poll(POLLIN, millis(self.timeout))? == 0 {
+            return Err(Error::ETIMEDOUT);
+        }
+        recv_reply(&self.socket)
+    }
+}
+
+fn connect(ctx: &Context, broker: &str) -> Result<Socket> {
+    let socket = ctx.socket(DEALER)?;
+    socket.set_linger(0)?;
+    socket.connect(broker)?;
+    Ok(socket)
+}
+
+fn send_request<T: AsRef<[u8]>>(socket: &Socket, service: &str, request: &[T]) -> Result<()> {
+    let header = [CLIENT.as_bytes(), C_REQUEST, service.as_bytes()];
+    let frames = header
+        .iter()
+        .copied()
+        .chain(request.iter().map(AsRef::as_ref));
+    socket.send_multipart(frames, 0)
+}
+
+fn recv_reply(socket: &Socket) -> Result<Reply> {
+    let mut frames = socket.recv_multipart(0)?;
+    if frames.len() < 3 || frames[0] != CLIENT.as_bytes() {
+        return Err(Error::EPROTO);
+    }
+    let is_final = match &frames[1][..] {
+        C_FINAL => true,
+        C_PARTIAL => false,
+        _ => return Err(Error::EPROTO),
+    };
+    let body = frames.split_off(3);
+    let service = frames.pop().unwrap();
+    Ok(Reply {
+        service,
+        body,
+        is_final,
+    })
+}
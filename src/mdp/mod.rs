@@ -0,0 +1,71 @@
+//! The Majordomo Protocol, MDP/0.2, for service-oriented request
+//! routing.
+//!
+//! Clients send requests for a named service to a `Broker`, which
+//! forwards each request to one of the `Worker`s that registered for
+//! that service, and routes the worker's replies back to the client.
+//! Workers and the broker detect each other's failure by exchanging
+//! heartbeats; workers reconnect after losing the broker. A reply may
+//! consist of any number of partial replies, followed by a final reply.
+//!
+//! The broker also implements the Majordomo Management Interface
+//! (MMI): a request for `mmi.service` with a service name as its body
+//! is answered with `200` if workers for the service are registered, or
+//! `404` otherwise. Other `mmi.` services are answered with `501`.
+//!
+//! Clients and workers connect to the broker with `DEALER` sockets, so
+//! messages carry no empty delimiter frame after the sender's identity.
+//!
+//! ```
+//! # fn main() -> zmq::Result<()> {
+//! use zmq::mdp::{Broker, Client, Worker};
+//!
+//! let ctx = zmq::Context::new();
+//! let mut broker = Broker::new(&ctx)?;
+//! broker.bind("inproc://mdp-doc")?;
+//! std::thread::spawn(move || broker.run());
+//!
+//! let mut worker = Worker::new(&ctx, "inproc://mdp-doc", "echo")?;
+//! std::thread::spawn(move || -> zmq::Result<()> {
+//!     loop {
+//!         let request = worker.recv()?;
+//!         worker.reply(&request, request.body())?;
+//!     }
+//! });
+//!
+//! let mut client = Client::new(&ctx, "inproc://mdp-doc")?;
+//! assert_eq!(client.send("echo", &["hello"])?, vec![b"hello"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+mod broker;
+mod client;
+mod worker;
+
+pub use self::broker::Broker;
+pub use self::client::{Client, PipelinedClient, Reply};
+pub use self::worker::{Request, Worker};
+
+/// The protocol header of messages between clients and the broker.
+pub const CLIENT: &str = "MDPC02";
+/// The protocol header of messages between workers and the broker.
+pub const WORKER: &str = "MDPW02";
+
+// Commands between clients and the broker.
+const C_REQUEST: &[u8] = b"\x01";
+const C_PARTIAL: &[u8] = b"\x02";
+const C_FINAL: &[u8] = b"\x03";
+
+// Commands between workers and the broker.
+const W_READY: &[u8] = b"\x01";
+const W_REQUEST: &[u8] = b"\x02";
+const W_PARTIAL: &[u8] = b"\x03";
+const W_FINAL: &[u8] = b"\x04";
+const W_HEARTBEAT: &[u8] = b"\x05";
+const W_DISCONNECT: &[u8] = b"\x06";
+
+/// The default interval between heartbeats.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(2500);
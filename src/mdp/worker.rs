@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use log::warn;
+
+use super::{
+    HEARTBEAT_INTERVAL, WORKER, W_DISCONNECT, W_FINAL, W_HEARTBEAT, W_PARTIAL, W_READY, W_REQUEST,
+};
+use crate::reliable::{millis, worker_request, Backoff, Heartbeat, HEARTBEAT_LIVENESS};
+use crate::{Context, Message, Result, Socket, DEALER, DONTWAIT};
+
+worker_request! {
+    /// A request received by a `Worker`.
+    client: Vec<u8>
+}
+
+/// A Majordomo worker, providing a service through a broker.
+///
+/// The worker exchanges heartbeats with the broker while waiting for a
+/// request in `recv()`. If the broker misses too many heartbeats, or
+/// tells the worker to disconnect, the worker waits for the reconnect
+/// interval, and registers with the broker again on a new socket.
+///
+/// While the worker handles a request, it is up to the caller to keep
+/// the broker informed: a worker that sends neither replies nor
+/// heartbeats for as long as the broker's expiry is considered dead,
+/// and its request is passed to another worker. Long-running handlers
+/// should call `heartbeat()` or send partial replies regularly.
+///
+/// Dropping the worker tells the broker to forget it.
+pub struct Worker {
+    ctx: Context,
+    broker: String,
+    service: Vec<u8>,
+    socket: Socket,
+    heartbeat: Heartbeat,
+    reconnect: Backoff,
+}
+
+impl Worker {
+    /// Connect to the broker at `broker`, and register for `service`.
+    pub fn new(ctx: &Context, broker: &str, service: &str) -> Result<Worker> {
+        let broker = broker.to_owned();
+        let socket = connect(ctx, &broker, service.as_bytes())?;
+        Ok(Worker {
+            ctx: ctx.clone(),
+            broker,
+            service: service.as_bytes().to_vec(),
+            socket,
+            heartbeat: Heartbeat::new(HEARTBEAT_INTERVAL, HEARTBEAT_LIVENESS),
+            reconnect: Backoff::new(HEARTBEAT_INTERVAL, HEARTBEAT_INTERVAL),
+        })
+    }
+
+    /// Set the interval between heartbeats sent to the broker, which
+    /// must match the broker's setting. Defaults to 2.5 seconds.
+    pub fn set_heartbeat_interval(&mut self, interval: Duration) {
+        self.heartbeat.set_interval(interval);
+    }
+
+    /// Set the number of heartbeat intervals after which a silent broker
+    /// is considered dead. Defaults to 3.
+    pub fn set_heartbeat_liveness(&mut self, liveness: u32) {
+        self.heartbeat.set_liveness(liveness);
+    }
+
+    /// Set the time to wait before reconnecting to the broker. Defaults
+    /// to 2.5 seconds.
+    pub fn set_reconnect_interval(&mut self, interval: Duration) {
+        self.reconnect.set_initial(interval);
+        self.reconnect.set_max(interval);
+    }
+
+    /// Wait for the next request.
+    pub fn recv(&mut self) -> Result<Request> {
+        loop {
+            let heartbeat = |socket: &Socket| send_command(socket, W_HEARTBEAT, 0);
+            let mut frames = match self.heartbeat.recv(&self.socket, heartbeat)? {
+                Some(frames) => frames,
+                None => {
+                    self.reconnect.wait();
+                    self.reconnect()?;
+                    continue;
+                }
+            };
+            if frames.len() < 2 || frames[0] != WORKER.as_bytes() {
+                warn!("mdp worker: ignoring invalid message");
+                continue;
+            }
+            match &frames[1][..] {
+                // The client's address is followed by an empty delimiter.
+                W_REQUEST if frames.len() >= 4 && frames[3].is_empty() => {
+                    let body = frames.split_off(4);
+                    let client = frames.swap_remove(2);
+                    return Ok(Request { client, body });
+                }
+                W_HEARTBEAT => {}
+                W_DISCONNECT => self.reconnect()?,
+                _ => warn!("mdp worker: ignoring invalid message"),
+            }
+        }
+    }
+
+    /// Tell the broker that the worker is still alive, e.g. while it is
+    /// busy with a request. `recv()` does this on its own.
+    pub fn heartbeat(&mut self) -> Result<()> {
+        send_command(&self.socket, W_HEARTBEAT, 0)?;
+        self.heartbeat.sent();
+        Ok(())
+    }
+
+    /// Send the final reply to `request`.
+    pub fn reply<I, T>(&self, request: &Request, body: I) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Message>,
+    {
+        self.send_reply(W_FINAL, request, body)
+    }
+
+    /// Send a partial reply to `request`, which is to be followed by
+    /// more replies.
+    pub fn reply_partial<I, T>(&self, request: &Request, body: I) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Message>,
+    {
+        self.send_reply(W_PARTIAL, request, body)
+    }
+
+    fn send_reply<I, T>(&self, command: &[u8], request: &Request, body: I) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Message>,
+    {
+        let header = [WORKER.as_bytes(), command, &request.client, b""];
+        let frames = header
+            .iter()
+            .map(|&frame| Message::from(frame))
+            .chain(body.into_iter().map(Into::into));
+        self.socket.send_multipart(frames, 0)
+    }
+
+    /// Register with the broker again, on a new socket.
+    fn reconnect(&mut self) -> Result<()> {
+        self.socket = connect(&self.ctx, &self.broker, &self.service)?;
+        self.heartbeat.reset();
+        Ok(())
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        // Give the command a chance to be delivered, without blocking
+        // for long if the broker is gone.
+        let _ = self
+            .socket
+            .set_linger(millis(self.heartbeat.interval()) as i32);
+        let _ = send_command(&self.socket, W_DISCONNECT, DONTWAIT);
+    }
+}
+
+fn send_command(socket: &Socket, command: &[u8], flags: i32) -> Result<()> {
+    socket.send_multipart([WORKER.as_bytes(), command], flags)
+}
+
+fn connect(ctx: &Context, broker: &str, service: &[u8]) -> Result<Socket> {
+    let socket = ctx.socket(DEALER)?;
+    socket.set_linger(0)?;
+    socket.connect(broker)?;
+    socket.send_multipart([WORKER.as_bytes(), W_READY, service], 0)?;
+    Ok(socket)
+}
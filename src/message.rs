@@ -1,4 +1,5 @@
 use libc::size_t;
+use log::error;
 
 use std::ffi;
 use std::fmt;
@@ -24,9 +25,9 @@ pub struct Message {
 
 impl Drop for Message {
     fn drop(&mut self) {
-        unsafe {
-            let rc = zmq_sys::zmq_msg_close(&mut self.msg);
-            assert_eq!(rc, 0);
+        let rc = unsafe { zmq_sys::zmq_msg_close(&mut self.msg) };
+        if rc == -1 {
+            error!("failed to close message: {}", errno_to_error());
         }
     }
 }
@@ -38,7 +39,10 @@ impl fmt::Debug for Message {
 }
 
 unsafe extern "C" fn drop_msg_data_box(data: *mut c_void, hint: *mut c_void) {
-    let _ = Box::from_raw(slice::from_raw_parts_mut(data as *mut u8, hint as usize));
+    let _ = Box::from_raw(ptr::slice_from_raw_parts_mut(
+        data as *mut u8,
+        hint as usize,
+    ));
 }
 
 impl Message {
@@ -157,6 +161,12 @@ impl Message {
     }
 }
 
+impl Default for Message {
+    fn default() -> Self {
+        Message::new()
+    }
+}
+
 impl Deref for Message {
     type Target = [u8];
 
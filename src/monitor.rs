@@ -0,0 +1,62 @@
+//! Decoding of socket monitor events.
+
+use std::convert::TryInto;
+
+use crate::{Error, Result, SocketEvent};
+
+/// An event read from a socket monitor, see `Socket::monitor()`.
+///
+/// libzmq sends each event as two frames: the event number and an
+/// event-specific value, followed by the affected endpoint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MonitorEvent {
+    event: SocketEvent,
+    raw_event: u16,
+    value: u32,
+    endpoint: String,
+}
+
+impl MonitorEvent {
+    /// Decode an event from the frames of a monitor message.
+    ///
+    /// Fails with `EPROTO` if the frames are not a valid event.
+    pub fn from_frames<T: AsRef<[u8]>>(frames: &[T]) -> Result<MonitorEvent> {
+        let (header, endpoint) = match frames {
+            [header, endpoint] => (header.as_ref(), endpoint.as_ref()),
+            _ => return Err(Error::EPROTO),
+        };
+        if header.len() != 6 {
+            return Err(Error::EPROTO);
+        }
+        let event = u16::from_ne_bytes(header[..2].try_into().unwrap());
+        let value = u32::from_ne_bytes(header[2..].try_into().unwrap());
+        Ok(MonitorEvent {
+            event: SocketEvent::from_raw(event),
+            raw_event: event,
+            value,
+            endpoint: String::from_utf8_lossy(endpoint).into_owned(),
+        })
+    }
+
+    /// The kind of event.
+    pub fn event(&self) -> SocketEvent {
+        self.event
+    }
+
+    /// The event number as sent by libzmq, which tells events apart
+    /// that `event()` reports as `SocketEvent::Unknown`.
+    pub fn raw_event(&self) -> u16 {
+        self.raw_event
+    }
+
+    /// The event-specific value, e.g. the file descriptor of an
+    /// accepted connection, or the error number of a failure.
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// The endpoint the event refers to.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+}
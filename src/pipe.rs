@@ -0,0 +1,25 @@
+//! Talking to background threads over inproc pipes.
+
+use std::thread::JoinHandle;
+
+use crate::{Error, Result, Socket, POLLIN};
+
+/// How often a parent waiting on the pipe to a background thread checks
+/// whether the thread is still running, in milliseconds.
+pub(crate) const REPLY_POLL_INTERVAL: i64 = 100;
+
+/// Receive a reply of the background thread `thread` on its `pipe`,
+/// failing with `EAGAIN` if it stops without sending one.
+pub(crate) fn recv_reply(pipe: &Socket, thread: &JoinHandle<Result<()>>) -> Result<Vec<Vec<u8>>> {
+    loop {
+        // Checked before polling, so that a reply sent right before the
+        // thread stopped is not missed.
+        let finished = thread.is_finished();
+        if pipe.poll(POLLIN, REPLY_POLL_INTERVAL)? > 0 {
+            return pipe.recv_multipart(0);
+        }
+        if finished {
+            return Err(Error::EAGAIN);
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! Reliable request-reply with the Pirate patterns.
+//!
+//! The Paranoid Pirate `Queue` balances requests from clients across
+//! `Worker`s. The queue and the workers exchange heartbeats: workers
+//! that stop sending them are dropped from the queue, and workers that
+//! lose the queue reconnect to it, backing off exponentially. The Lazy
+//! Pirate `Client` retries a request on a fresh socket when no reply
+//! arrives in time.
+//!
+//! Workers announce themselves with a single `READY` frame (`0x01`);
+//! heartbeats are a single `HEARTBEAT` frame (`0x02`). On top of that,
+//! the queue and workers enable ZMTP heartbeats (`ZMQ_HEARTBEAT_IVL`
+//! and `ZMQ_HEARTBEAT_TIMEOUT`), so that libzmq also drops TCP
+//! connections to peers that have gone silent.
+//!
+//! ```
+//! # fn main() -> zmq::Result<()> {
+//! use zmq::pirate::{Client, Queue, Worker};
+//!
+//! let ctx = zmq::Context::new();
+//! let mut queue = Queue::new(&ctx)?;
+//! queue.bind_frontend("inproc://pirate-doc-front")?;
+//! queue.bind_backend("inproc://pirate-doc-back")?;
+//! std::thread::spawn(move || queue.run());
+//!
+//! let mut worker = Worker::new(&ctx, "inproc://pirate-doc-back")?;
+//! std::thread::spawn(move || -> zmq::Result<()> {
+//!     loop {
+//!         let request = worker.recv()?;
+//!         worker.reply(&request, request.body())?;
+//!     }
+//! });
+//!
+//! let mut client = Client::new(&ctx, "inproc://pirate-doc-front")?;
+//! assert_eq!(client.send(&["hello"])?, vec![b"hello"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use log::warn;
+
+use crate::reliable::{millis, worker_request, Backoff, Heartbeat, HEARTBEAT_LIVENESS};
+use crate::{poll, Context, Error, Message, Result, Socket, DEALER, POLLIN, REQ, ROUTER};
+
+/// The signal a worker sends when it is ready for requests.
+pub const READY: &[u8] = b"\x01";
+/// The signal the queue and workers send to show they are alive.
+pub const HEARTBEAT: &[u8] = b"\x02";
+
+/// The default interval between heartbeats.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(1000);
+/// The default initial and maximum times to wait before reconnecting.
+const RECONNECT_INTERVAL: Duration = Duration::from_millis(1000);
+const RECONNECT_INTERVAL_MAX: Duration = Duration::from_millis(32000);
+/// The default time a client waits for a reply.
+const REQUEST_TIMEOUT: Duration = Duration::from_millis(2500);
+/// The default number of attempts a client makes at getting a reply.
+const REQUEST_RETRIES: u32 = 3;
+
+/// Enable ZMTP heartbeats on `socket`, dropping the connection if the
+/// peer is silent for `liveness` intervals.
+fn set_zmtp_heartbeat(socket: &Socket, interval: Duration, liveness: u32) -> Result<()> {
+    socket.set_heartbeat_ivl(millis(interval) as i32)?;
+    socket.set_heartbeat_timeout(millis(interval * liveness) as i32)
+}
+
+/// A Paranoid Pirate queue, passing requests from clients to the
+/// worker that has been ready the longest.
+///
+/// Clients connect to the frontend with `REQ` sockets, or anything
+/// sending the same envelope; workers connect to the backend. Requests
+/// are only read from the frontend while at least one worker is ready.
+pub struct Queue {
+    frontend: Socket,
+    backend: Socket,
+    /// Ready workers and their expiry times, the longest ready first.
+    workers: VecDeque<(Vec<u8>, Instant)>,
+    heartbeat_interval: Duration,
+    heartbeat_liveness: u32,
+}
+
+impl Queue {
+    /// Create a queue with new frontend and backend `ROUTER` sockets.
+    pub fn new(ctx: &Context) -> Result<Queue> {
+        let backend = ctx.socket(ROUTER)?;
+        set_zmtp_heartbeat(&backend, HEARTBEAT_INTERVAL, HEARTBEAT_LIVENESS)?;
+        Ok(Queue {
+            frontend: ctx.socket(ROUTER)?,
+            backend,
+            workers: VecDeque::new(),
+            heartbeat_interval: HEARTBEAT_INTERVAL,
+            heartbeat_liveness: HEARTBEAT_LIVENESS,
+        })
+    }
+
+    /// Accept clients on `endpoint`, see `Socket::bind()`.
+    pub fn bind_frontend(&self, endpoint: &str) -> Result<()> {
+        self.frontend.bind(endpoint)
+    }
+
+    /// Accept workers on `endpoint`, see `Socket::bind()`.
+    pub fn bind_backend(&self, endpoint: &str) -> Result<()> {
+        self.backend.bind(endpoint)
+    }
+
+    /// Get a reference to the queue's frontend socket.
+    pub fn get_frontend_ref(&self) -> &Socket {
+        &self.frontend
+    }
+
+    /// Get a reference to the queue's backend socket.
+    pub fn get_backend_ref(&self) -> &Socket {
+        &self.backend
+    }
+
+    /// Set the interval between heartbeats, which must match the
+    /// workers' setting. Defaults to one second.
+    ///
+    /// This only affects ZMTP heartbeats of connections made afterwards.
+    pub fn set_heartbeat_interval(&mut self, interval: Duration) -> Result<()> {
+        set_zmtp_heartbeat(&self.backend, interval, self.heartbeat_liveness)?;
+        self.heartbeat_interval = interval;
+        Ok(())
+    }
+
+    /// Set the number of heartbeat intervals after which a silent
+    /// worker is dropped. Defaults to 3.
+    pub fn set_heartbeat_liveness(&mut self, liveness: u32) -> Result<()> {
+        set_zmtp_heartbeat(&self.backend, self.heartbeat_interval, liveness)?;
+        self.heartbeat_liveness = liveness;
+        Ok(())
+    }
+
+    /// Run the queue in the current thread.
+    ///
+    /// This only returns (always with an `Err`) when an operation on one
+    /// of the sockets fails, typically with `ETERM` because the context
+    /// has been shut down.
+    pub fn run(&mut self) -> Result<()> {
+        let mut heartbeat_at = Instant::now() + self.heartbeat_interval;
+        loop {
+            let timeout = heartbeat_at.saturating_duration_since(Instant::now());
+            let (backend_ready, frontend_ready) = {
+                let mut items = [
+                    self.backend.as_poll_item(POLLIN),
+                    self.frontend.as_poll_item(POLLIN),
+                ];
+                let polled = if self.workers.is_empty() { 1 } else { 2 };
+                poll(&mut items[..polled], millis(timeout))?;
+                (items[0].is_readable(), items[1].is_readable())
+            };
+            if backend_ready {
+                let mut frames = self.backend.recv_multipart(0)?;
+                let identity = frames.remove(0);
+                self.worker_ready(identity);
+                match &frames[..] {
+                    [signal] if signal == READY || signal == HEARTBEAT => {}
+                    [_] => warn!("pirate queue: ignoring invalid message from worker"),
+                    _ => self.frontend.send_multipart(frames, 0)?,
+                }
+            }
+            if frontend_ready {
+                let frames = self.frontend.recv_multipart(0)?;
+                if let Some((identity, _)) = self.workers.pop_front() {
+                    let frames = Some(identity).into_iter().chain(frames);
+                    self.backend.send_multipart(frames, 0)?;
+                }
+            }
+            if Instant::now() >= heartbeat_at {
+                for (identity, _) in &self.workers {
+                    self.backend.send_multipart([&identity[..], HEARTBEAT], 0)?;
+                }
+                heartbeat_at = Instant::now() + self.heartbeat_interval;
+            }
+            let now = Instant::now();
+            self.workers.retain(|(_, expiry)| *expiry >= now);
+        }
+    }
+
+    /// Put a worker at the end of the ready list, with a new expiry.
+    fn worker_ready(&mut self, identity: Vec<u8>) {
+        self.workers.retain(|(worker, _)| *worker != identity);
+        let expiry = Instant::now() + self.heartbeat_interval * self.heartbeat_liveness;
+        self.workers.push_back((identity, expiry));
+    }
+}
+
+worker_request! {
+    /// A request received by a `Worker`.
+    envelope: Vec<Vec<u8>>
+}
+
+/// A Paranoid Pirate worker, serving requests from a `Queue`.
+///
+/// The worker exchanges heartbeats with the queue while waiting for a
+/// request in `recv()`. If the queue misses too many heartbeats, the
+/// worker reconnects on a new socket, waiting twice as long before each
+/// further attempt, up to a maximum.
+pub struct Worker {
+    ctx: Context,
+    queue: String,
+    socket: Socket,
+    heartbeat: Heartbeat,
+    reconnect: Backoff,
+}
+
+impl Worker {
+    /// Connect to the queue's backend at `queue`, and signal that the
+    /// worker is ready.
+    pub fn new(ctx: &Context, queue: &str) -> Result<Worker> {
+        let queue = queue.to_owned();
+        let heartbeat = Heartbeat::new(HEARTBEAT_INTERVAL, HEARTBEAT_LIVENESS);
+        let socket = connect_worker(ctx, &queue, &heartbeat)?;
+        Ok(Worker {
+            ctx: ctx.clone(),
+            socket,
+            queue,
+            heartbeat,
+            reconnect: Backoff::new(RECONNECT_INTERVAL, RECONNECT_INTERVAL_MAX),
+        })
+    }
+
+    /// Set the interval between heartbeats, which must match the
+    /// queue's setting. Defaults to one second.
+    ///
+    /// This only affects ZMTP heartbeats after the next reconnect.
+    pub fn set_heartbeat_interval(&mut self, interval: Duration) {
+        self.heartbeat.set_interval(interval);
+    }
+
+    /// Set the number of heartbeat intervals after which a silent queue
+    /// is considered dead. Defaults to 3.
+    pub fn set_heartbeat_liveness(&mut self, liveness: u32) {
+        self.heartbeat.set_liveness(liveness);
+    }
+
+    /// Set the time to wait before the first attempt at reconnecting.
+    /// Defaults to one second.
+    pub fn set_reconnect_interval(&mut self, interval: Duration) {
+        self.reconnect.set_initial(interval);
+    }
+
+    /// Set the maximum time to wait before reconnecting. Defaults to 32
+    /// seconds.
+    pub fn set_reconnect_interval_max(&mut self, interval: Duration) {
+        self.reconnect.set_max(interval);
+    }
+
+    /// Wait for the next request.
+    pub fn recv(&mut self) -> Result<Request> {
+        loop {
+            let heartbeat = |socket: &Socket| socket.send(HEARTBEAT, 0);
+            let mut frames = match self.heartbeat.recv(&self.socket, heartbeat)? {
+                Some(frames) => frames,
+                None => {
+                    warn!(
+                        "pirate worker: queue is unreachable, reconnecting in {:?}",
+                        self.reconnect.current()
+                    );
+                    self.reconnect.wait();
+                    self.reconnect()?;
+                    continue;
+                }
+            };
+            self.reconnect.reset();
+            match frames.iter().position(Vec::is_empty) {
+                Some(delimiter) if frames.len() > 1 => {
+                    let body = frames.split_off(delimiter + 1);
+                    return Ok(Request {
+                        envelope: frames,
+                        body,
+                    });
+                }
+                _ if frames == [HEARTBEAT] => {}
+                _ => warn!("pirate worker: ignoring invalid message"),
+            }
+        }
+    }
+
+    /// Send the reply to `request`.
+    pub fn reply<I, T>(&self, request: &Request, body: I) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Message>,
+    {
+        let frames = request
+            .envelope
+            .iter()
+            .map(Message::from)
+            .chain(body.into_iter().map(Into::into));
+        self.socket.send_multipart(frames, 0)
+    }
+
+    /// Connect to the queue again, on a new socket.
+    fn reconnect(&mut self) -> Result<()> {
+        self.socket = connect_worker(&self.ctx, &self.queue, &self.heartbeat)?;
+        self.heartbeat.reset();
+        Ok(())
+    }
+}
+
+/// Connect a worker to the queue at `queue`, and signal that it is
+/// ready.
+fn connect_worker(ctx: &Context, queue: &str, heartbeat: &Heartbeat) -> Result<Socket> {
+    let socket = ctx.socket(DEALER)?;
+    socket.set_linger(0)?;
+    set_zmtp_heartbeat(&socket, heartbeat.interval(), heartbeat.liveness())?;
+    socket.connect(queue)?;
+    socket.send(READY, 0)?;
+    Ok(socket)
+}
+
+/// A Lazy Pirate client, sending requests over a `REQ` socket.
+///
+/// If no reply arrives within the timeout, the client closes its
+/// socket, opens a new one, and sends the request again, up to the
+/// configured number of attempts. Requests must therefore be
+/// idempotent.
+pub struct Client {
+    ctx: Context,
+    server: String,
+    socket: Socket,
+    timeout: Duration,
+    retries: u32,
+}
+
+impl Client {
+    /// Connect to the server at `server`, e.g. a queue's frontend.
+    pub fn new(ctx: &Context, server: &str) -> Result<Client> {
+        let server = server.to_owned();
+        let socket = connect_client(ctx, &server)?;
+        Ok(Client {
+            ctx: ctx.clone(),
+            server,
+            socket,
+            timeout: REQUEST_TIMEOUT,
+            retries: REQUEST_RETRIES,
+        })
+    }
+
+    /// Set the time to wait for a reply. Defaults to 2.5 seconds.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Set the number of attempts at getting a reply. Defaults to 3.
+    pub fn set_retries(&mut self, retries: u32) {
+        self.retries = retries;
+    }
+
+    /// Send a request, and wait for the reply.
+    ///
+    /// Fails with `ETIMEDOUT` if no reply arrives after the configured
+    /// number of attempts.
+    pub fn send<T: AsRef<[u8]>>(&mut self, request: &[T]) -> Result<Vec<Vec<u8>>> {
+        for _ in 0..self.retries {
+            self.socket
+                .send_multipart(request.iter().map(AsRef::as_ref), 0)?;
+            if self.socket.poll(POLLIN, millis(self.timeout))? > 0 {
+                return self.socket.recv_multipart(0);
+            }
+            // A `REQ` socket cannot send again before it has received a
+            // reply, so start over with a new one.
+            self.socket = connect_client(&self.ctx, &self.server)?;
+        }
+        Err(Error::ETIMEDOUT)
+    }
+}
+
+fn connect_client(ctx: &Context, server: &str) -> Result<Socket> {
+    let socket = ctx.socket(REQ)?;
+    socket.set_linger(0)?;
+    socket.connect(server)?;
+    Ok(socket)
+}
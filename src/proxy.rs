@@ -0,0 +1,392 @@
+//! Proxies running in Rust or on background threads.
+
+use std::convert::TryInto;
+use std::thread::{self, JoinHandle};
+
+use crate::pipe::recv_reply;
+use crate::{poll, proxy_steerable, Error, Message, Result, Socket, DONTWAIT, POLLIN, SNDMORE};
+
+/// What a `Proxy` does with a message once a hook has seen it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProxyAction {
+    /// Forward the message, including any changes made by the hook, to
+    /// the other side of the proxy.
+    Pass,
+    /// Discard the message.
+    Drop,
+    /// Send the message, including any changes made by the hook, back
+    /// out of the socket it was received on, e.g. to answer a request
+    /// without involving the other side.
+    Redirect,
+}
+
+type Hook<'a> = Box<dyn FnMut(&mut Vec<Message>) -> ProxyAction + 'a>;
+
+/// A proxy between a frontend and a backend socket.
+///
+/// This does the same job as `proxy()`, but the forwarding loop runs in
+/// Rust on top of `poll()`, which allows hooks to see each multipart
+/// message before it is forwarded. A hook may modify the message in
+/// place, and decides what happens to it by returning a `ProxyAction`.
+///
+/// ```no_run
+/// # fn main() -> zmq::Result<()> {
+/// let ctx = zmq::Context::new();
+/// let frontend = ctx.socket(zmq::XSUB)?;
+/// let backend = ctx.socket(zmq::XPUB)?;
+/// // Only publish topics from the `public.` namespace.
+/// zmq::Proxy::new(&frontend, &backend)
+///     .on_frontend_msg(|msg| {
+///         if msg[0].starts_with(b"public.") {
+///             zmq::ProxyAction::Pass
+///         } else {
+///             zmq::ProxyAction::Drop
+///         }
+///     })
+///     .run()
+/// # }
+/// ```
+pub struct Proxy<'a> {
+    frontend: &'a Socket,
+    backend: &'a Socket,
+    capture: Option<&'a Socket>,
+    on_frontend_msg: Option<Hook<'a>>,
+    on_backend_msg: Option<Hook<'a>>,
+}
+
+/// The side of the proxy a message was received on.
+#[derive(Clone, Copy)]
+enum Side {
+    Frontend,
+    Backend,
+}
+
+impl<'a> Proxy<'a> {
+    /// Create a proxy forwarding between `frontend` and `backend`.
+    pub fn new(frontend: &'a Socket, backend: &'a Socket) -> Proxy<'a> {
+        Proxy {
+            frontend,
+            backend,
+            capture: None,
+            on_frontend_msg: None,
+            on_backend_msg: None,
+        }
+    }
+
+    /// Send a copy of every message received on either side to
+    /// `capture`.
+    ///
+    /// The copy is taken before any hook runs, so it includes messages
+    /// that are later dropped.
+    pub fn capture(mut self, capture: &'a Socket) -> Proxy<'a> {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Run `hook` on every message received on the frontend.
+    pub fn on_frontend_msg<F>(mut self, hook: F) -> Proxy<'a>
+    where
+        F: FnMut(&mut Vec<Message>) -> ProxyAction + 'a,
+    {
+        self.on_frontend_msg = Some(Box::new(hook));
+        self
+    }
+
+    /// Run `hook` on every message received on the backend.
+    pub fn on_backend_msg<F>(mut self, hook: F) -> Proxy<'a>
+    where
+        F: FnMut(&mut Vec<Message>) -> ProxyAction + 'a,
+    {
+        self.on_backend_msg = Some(Box::new(hook));
+        self
+    }
+
+    /// Run the proxy in the current thread.
+    ///
+    /// Like `proxy()`, this only returns (always with an `Err`) when an
+    /// operation on one of the sockets fails, typically with `ETERM`
+    /// because the context has been shut down.
+    pub fn run(mut self) -> Result<()> {
+        loop {
+            let (frontend_ready, backend_ready) = {
+                let mut items = [
+                    self.frontend.as_poll_item(POLLIN),
+                    self.backend.as_poll_item(POLLIN),
+                ];
+                poll(&mut items, -1)?;
+                (items[0].is_readable(), items[1].is_readable())
+            };
+            if frontend_ready {
+                self.forward(Side::Frontend)?;
+            }
+            if backend_ready {
+                self.forward(Side::Backend)?;
+            }
+        }
+    }
+
+    /// Receive one message on `side`, and act on it.
+    fn forward(&mut self, side: Side) -> Result<()> {
+        let (source, sink, hook) = match side {
+            Side::Frontend => (self.frontend, self.backend, &mut self.on_frontend_msg),
+            Side::Backend => (self.backend, self.frontend, &mut self.on_backend_msg),
+        };
+        let mut parts = recv_parts(source, 0)?;
+        if let Some(capture) = self.capture {
+            send_copy(capture, &parts)?;
+        }
+        let action = match hook {
+            Some(hook) => hook(&mut parts),
+            None => ProxyAction::Pass,
+        };
+        match action {
+            ProxyAction::Pass => sink.send_multipart(parts, 0),
+            ProxyAction::Redirect => source.send_multipart(parts, 0),
+            ProxyAction::Drop => Ok(()),
+        }
+    }
+}
+
+/// Receive all parts of a message, without copying them.
+///
+/// `flags` only applies to the first part: libzmq delivers messages
+/// atomically, so the remaining parts are available right away.
+pub(crate) fn recv_parts(socket: &Socket, flags: i32) -> Result<Vec<Message>> {
+    let mut parts = vec![socket.recv_msg(flags)?];
+    while parts[parts.len() - 1].get_more() {
+        parts.push(socket.recv_msg(DONTWAIT)?);
+    }
+    Ok(parts)
+}
+
+fn send_copy(socket: &Socket, parts: &[Message]) -> Result<()> {
+    for (i, part) in parts.iter().enumerate() {
+        let flags = if i + 1 < parts.len() { SNDMORE } else { 0 };
+        socket.send(&part[..], flags)?;
+    }
+    Ok(())
+}
+
+/// Message and byte counts of a steerable proxy, see
+/// `ProxyController::statistics()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProxyStats {
+    /// Number of messages received on the frontend.
+    pub frontend_msgs_in: u64,
+    /// Number of bytes received on the frontend.
+    pub frontend_bytes_in: u64,
+    /// Number of messages sent on the frontend.
+    pub frontend_msgs_out: u64,
+    /// Number of bytes sent on the frontend.
+    pub frontend_bytes_out: u64,
+    /// Number of messages received on the backend.
+    pub backend_msgs_in: u64,
+    /// Number of bytes received on the backend.
+    pub backend_bytes_in: u64,
+    /// Number of messages sent on the backend.
+    pub backend_msgs_out: u64,
+    /// Number of bytes sent on the backend.
+    pub backend_bytes_out: u64,
+}
+
+impl ProxyStats {
+    /// Decode the reply to a `STATISTICS` command, which consists of
+    /// eight frames holding a native-endian `u64` each.
+    ///
+    /// Fails with `EPROTO` if the frames are not a valid reply.
+    fn from_frames<T: AsRef<[u8]>>(frames: &[T]) -> Result<ProxyStats> {
+        if frames.len() != 8 {
+            return Err(Error::EPROTO);
+        }
+        let mut counts = [0; 8];
+        for (count, frame) in counts.iter_mut().zip(frames) {
+            let bytes = frame.as_ref().try_into().map_err(|_| Error::EPROTO)?;
+            *count = u64::from_ne_bytes(bytes);
+        }
+        Ok(ProxyStats {
+            frontend_msgs_in: counts[0],
+            frontend_bytes_in: counts[1],
+            frontend_msgs_out: counts[2],
+            frontend_bytes_out: counts[3],
+            backend_msgs_in: counts[4],
+            backend_bytes_in: counts[5],
+            backend_msgs_out: counts[6],
+            backend_bytes_out: counts[7],
+        })
+    }
+}
+
+/// Run `proxy_steerable()` on a background thread.
+///
+/// The sockets are moved to a thread named `zmq-proxy`, and the proxy
+/// is steered through the returned controller, which is connected to it
+/// by an inproc `PAIR` socket. Both sockets must have been created from
+/// a `Context`, otherwise this fails with `EINVAL`.
+pub fn spawn_proxy_steerable(frontend: Socket, backend: Socket) -> Result<ProxyController> {
+    let ctx = frontend.context.clone().ok_or(Error::EINVAL)?;
+    if backend.context.is_none() {
+        return Err(Error::EINVAL);
+    }
+    let (control, mut proxy_control) = ctx.pipe()?;
+
+    let (mut frontend, mut backend) = (frontend, backend);
+    let thread = thread::Builder::new()
+        .name("zmq-proxy".into())
+        .spawn(move || proxy_steerable(&mut frontend, &mut backend, &mut proxy_control))
+        .map_err(|_| Error::EAGAIN)?;
+    Ok(ProxyController {
+        control,
+        thread: Some(thread),
+    })
+}
+
+/// A handle for steering a proxy started by `spawn_proxy_steerable()`.
+///
+/// Dropping the controller terminates the proxy and waits for its
+/// thread to finish.
+pub struct ProxyController {
+    control: Socket,
+    thread: Option<JoinHandle<Result<()>>>,
+}
+
+impl ProxyController {
+    /// Suspend forwarding messages until `resume()` is called.
+    ///
+    /// Fails with `EAGAIN` if the proxy has stopped.
+    pub fn pause(&self) -> Result<()> {
+        self.command(if proxy_quirks().pause_resume_swapped {
+            "RESUME"
+        } else {
+            "PAUSE"
+        })
+    }
+
+    /// Resume forwarding messages after `pause()`.
+    ///
+    /// Fails with `EAGAIN` if the proxy has stopped.
+    pub fn resume(&self) -> Result<()> {
+        self.command(if proxy_quirks().pause_resume_swapped {
+            "PAUSE"
+        } else {
+            "RESUME"
+        })
+    }
+
+    /// Query the message and byte counts of the proxy.
+    ///
+    /// Fails with `EAGAIN` if the proxy has stopped.
+    pub fn statistics(&self) -> Result<ProxyStats> {
+        self.control.send("STATISTICS", DONTWAIT)?;
+        ProxyStats::from_frames(&self.reply()?)
+    }
+
+    /// Terminate the proxy, and wait for its thread to finish.
+    ///
+    /// Returns the error the proxy failed with, if it did not run
+    /// until terminated, e.g. `ETERM` if the context was shut down.
+    pub fn terminate(mut self) -> Result<()> {
+        self.terminate_inner()
+    }
+
+    fn terminate_inner(&mut self) -> Result<()> {
+        let thread = match self.thread.take() {
+            Some(thread) => thread,
+            None => return Ok(()),
+        };
+        // If the proxy has already stopped, there is nobody to receive
+        // the command, and its result is all there is to report. There
+        // is no need to wait for an acknowledgement, as joining the
+        // thread waits for the proxy to stop.
+        let _ = self.control.send("TERMINATE", DONTWAIT);
+        thread.join().unwrap_or(Err(Error::EFAULT))
+    }
+
+    /// Send a command, and wait for the proxy to acknowledge it if it
+    /// does so.
+    fn command(&self, command: &str) -> Result<()> {
+        self.control.send(command, DONTWAIT)?;
+        if proxy_quirks().acks_commands {
+            self.reply()?;
+        }
+        Ok(())
+    }
+
+    /// Receive the reply to a command, failing with `EAGAIN` if the
+    /// proxy stops without sending one.
+    fn reply(&self) -> Result<Vec<Vec<u8>>> {
+        match self.thread {
+            Some(ref thread) => recv_reply(&self.control, thread),
+            None => Err(Error::EAGAIN),
+        }
+    }
+}
+
+/// How the steerable proxy of the libzmq in use deviates from its
+/// documentation.
+#[derive(Clone, Copy, Debug)]
+struct ProxyQuirks {
+    /// Every command but `STATISTICS` is answered with an empty reply,
+    /// as done since libzmq 4.3.5.
+    acks_commands: bool,
+    /// `PAUSE` and `RESUME` each have the effect of the other, as in
+    /// libzmq 4.3.5.
+    pause_resume_swapped: bool,
+}
+
+/// Return the quirks of the steerable proxy of the libzmq in use.
+fn proxy_quirks() -> ProxyQuirks {
+    let version = crate::version();
+    ProxyQuirks {
+        acks_commands: version >= (4, 3, 5),
+        pause_resume_swapped: version == (4, 3, 5),
+    }
+}
+
+impl Drop for ProxyController {
+    fn drop(&mut self) {
+        let _ = self.terminate_inner();
+    }
+}
+
+/// Run `proxy()` on a background thread.
+///
+/// The sockets are moved to a thread named `zmq-proxy`. Unlike with
+/// `proxy()`, the proxy can be stopped: dropping the returned handle,
+/// or calling `ProxyHandle::join()`, terminates it through a control
+/// socket and waits for the thread to finish. Both sockets must have
+/// been created from a `Context`, otherwise this fails with `EINVAL`.
+///
+/// ```
+/// # fn main() -> zmq::Result<()> {
+/// let ctx = zmq::Context::new();
+/// let frontend = ctx.socket(zmq::ROUTER)?;
+/// frontend.bind("inproc://spawn-proxy-doc")?;
+/// let backend = ctx.socket(zmq::DEALER)?;
+/// let proxy = zmq::spawn_proxy(frontend, backend)?;
+/// // ...
+/// proxy.join()
+/// # }
+/// ```
+pub fn spawn_proxy(frontend: Socket, backend: Socket) -> Result<ProxyHandle> {
+    let controller = spawn_proxy_steerable(frontend, backend)?;
+    Ok(ProxyHandle { controller })
+}
+
+/// A handle for a proxy started by `spawn_proxy()`.
+///
+/// Dropping the handle terminates the proxy and waits for its thread
+/// to finish.
+pub struct ProxyHandle {
+    controller: ProxyController,
+}
+
+impl ProxyHandle {
+    /// Terminate the proxy, and wait for its thread to finish.
+    ///
+    /// Returns the error the proxy failed with, if it did not run
+    /// until terminated, e.g. `ETERM` if the context was shut down.
+    pub fn join(self) -> Result<()> {
+        self.controller.terminate()
+    }
+}
@@ -0,0 +1,191 @@
+//! Opt-in bookkeeping of the live sockets of a context.
+
+use std::backtrace::Backtrace;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+use crate::{Endpoints, SocketType};
+
+/// Diagnostic information about a live socket.
+///
+/// Socket information is only collected for contexts that have the
+/// socket registry enabled, see `ContextBuilder::socket_registry()`.
+#[derive(Clone)]
+pub struct SocketInfo {
+    socket_type: SocketType,
+    backtrace: Arc<Backtrace>,
+    bound: Vec<String>,
+    connected: Vec<String>,
+    thread_id: ThreadId,
+    thread_name: Option<String>,
+}
+
+impl SocketInfo {
+    /// The type of the socket.
+    pub fn socket_type(&self) -> SocketType {
+        self.socket_type
+    }
+
+    /// The backtrace captured when the socket was created.
+    pub fn backtrace(&self) -> &Backtrace {
+        &self.backtrace
+    }
+
+    /// The endpoints the socket is currently bound to.
+    ///
+    /// For wildcard binds, the endpoint as resolved by libzmq is
+    /// recorded.
+    pub fn bound_endpoints(&self) -> &[String] {
+        &self.bound
+    }
+
+    /// The endpoints the socket is currently connected to.
+    pub fn connected_endpoints(&self) -> &[String] {
+        &self.connected
+    }
+
+    /// The ID of the thread that last created, bound or connected the
+    /// socket.
+    ///
+    /// As sockets can be moved between threads, this is the best
+    /// available approximation of the thread owning the socket.
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+
+    /// The name of the thread identified by `thread_id()`, if any.
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread_name.as_deref()
+    }
+
+    fn touch(&mut self) {
+        let current = thread::current();
+        self.thread_id = current.id();
+        self.thread_name = current.name().map(String::from);
+    }
+}
+
+impl fmt::Debug for SocketInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SocketInfo")
+            .field("socket_type", &self.socket_type)
+            .field("bound", &self.bound)
+            .field("connected", &self.connected)
+            .field("thread_id", &self.thread_id)
+            .field("thread_name", &self.thread_name)
+            .finish()
+    }
+}
+
+impl fmt::Display for SocketInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} socket", self.socket_type)?;
+        if !self.bound.is_empty() {
+            write!(f, " bound to {}", self.bound.join(", "))?;
+        }
+        if !self.connected.is_empty() {
+            write!(f, " connected to {}", self.connected.join(", "))?;
+        }
+        match self.thread_name {
+            Some(ref name) => write!(f, " on thread '{}'", name)?,
+            None => write!(f, " on thread {:?}", self.thread_id)?,
+        }
+        write!(f, ", created at:\n{}", self.backtrace)
+    }
+}
+
+/// A live socket, with the endpoints shared with its `Socket`.
+struct Entry {
+    info: SocketInfo,
+    endpoints: Arc<Mutex<Endpoints>>,
+}
+
+impl Entry {
+    /// Return the socket information, with the current endpoints.
+    fn info(&self) -> SocketInfo {
+        let mut info = self.info.clone();
+        let endpoints = self.endpoints.lock().unwrap_or_else(|e| e.into_inner());
+        info.bound = endpoints
+            .bound
+            .iter()
+            .map(|bound| bound.resolved.as_str().to_owned())
+            .collect();
+        info.connected = endpoints
+            .connected
+            .iter()
+            .map(|e| e.as_str().to_owned())
+            .collect();
+        info
+    }
+}
+
+/// The live sockets of a context, keyed by their raw pointer.
+#[derive(Default)]
+pub(crate) struct SocketRegistry {
+    sockets: Mutex<HashMap<usize, Entry>>,
+    closed: Condvar,
+}
+
+impl SocketRegistry {
+    fn lock(&self) -> MutexGuard<'_, HashMap<usize, Entry>> {
+        // The map is always left in a consistent state, so recovering
+        // from poisoning is fine.
+        self.sockets.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn register(&self, key: usize, socket_type: SocketType, endpoints: Arc<Mutex<Endpoints>>) {
+        let current = thread::current();
+        let info = SocketInfo {
+            socket_type,
+            backtrace: Arc::new(Backtrace::force_capture()),
+            bound: Vec::new(),
+            connected: Vec::new(),
+            thread_id: current.id(),
+            thread_name: current.name().map(String::from),
+        };
+        self.lock().insert(key, Entry { info, endpoints });
+    }
+
+    pub fn deregister(&self, key: usize) {
+        let mut sockets = self.lock();
+        sockets.remove(&key);
+        if sockets.is_empty() {
+            self.closed.notify_all();
+        }
+    }
+
+    /// Record that the socket `key` has been used by the current
+    /// thread.
+    pub fn touch(&self, key: usize) {
+        if let Some(entry) = self.lock().get_mut(&key) {
+            entry.info.touch();
+        }
+    }
+
+    pub fn live_sockets(&self) -> Vec<SocketInfo> {
+        self.lock().values().map(Entry::info).collect()
+    }
+
+    /// Wait until all sockets have been closed, or `timeout` has
+    /// elapsed. In the latter case, the still-open sockets are
+    /// returned.
+    pub fn wait_closed(&self, timeout: Duration) -> Vec<SocketInfo> {
+        let deadline = Instant::now() + timeout;
+        let mut sockets = self.lock();
+        while !sockets.is_empty() {
+            let now = Instant::now();
+            if now >= deadline {
+                return sockets.values().map(Entry::info).collect();
+            }
+            sockets = self
+                .closed
+                .wait_timeout(sockets, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        Vec::new()
+    }
+}
@@ -0,0 +1,168 @@
+//! Building blocks shared by the reliability patterns: heartbeating,
+//! reconnecting with back-off, and the requests workers receive.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Result, Socket, POLLIN};
+
+/// The default number of heartbeats that may be missed before a peer
+/// is considered dead.
+pub(crate) const HEARTBEAT_LIVENESS: u32 = 3;
+
+/// Convert a duration to a `poll()` timeout, saturating at the largest
+/// one.
+pub(crate) fn millis(duration: Duration) -> i64 {
+    duration.as_millis().min(i64::MAX as u128) as i64
+}
+
+/// A worker's heartbeats with the broker or queue it gets requests
+/// from: when to send the next one, and how many more the peer may
+/// miss.
+pub(crate) struct Heartbeat {
+    interval: Duration,
+    liveness: u32,
+    remaining: u32,
+    send_at: Instant,
+}
+
+impl Heartbeat {
+    pub(crate) fn new(interval: Duration, liveness: u32) -> Heartbeat {
+        Heartbeat {
+            interval,
+            liveness,
+            remaining: liveness,
+            send_at: Instant::now() + interval,
+        }
+    }
+
+    pub(crate) fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub(crate) fn liveness(&self) -> u32 {
+        self.liveness
+    }
+
+    pub(crate) fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+        self.send_at = Instant::now() + interval;
+    }
+
+    pub(crate) fn set_liveness(&mut self, liveness: u32) {
+        self.liveness = liveness;
+        self.remaining = liveness;
+    }
+
+    /// Start over, e.g. on a new connection.
+    pub(crate) fn reset(&mut self) {
+        self.remaining = self.liveness;
+        self.send_at = Instant::now() + self.interval;
+    }
+
+    /// Record that a heartbeat has just been sent.
+    pub(crate) fn sent(&mut self) {
+        self.send_at = Instant::now() + self.interval;
+    }
+
+    /// Wait for the next message from the peer on `socket`, calling
+    /// `send` whenever a heartbeat is due.
+    ///
+    /// Returns `None` once the peer has been silent for `liveness`
+    /// intervals, in which case the caller should reconnect.
+    pub(crate) fn recv<F>(&mut self, socket: &Socket, send: F) -> Result<Option<Vec<Vec<u8>>>>
+    where
+        F: Fn(&Socket) -> Result<()>,
+    {
+        loop {
+            let frames = if socket.poll(POLLIN, millis(self.interval))? > 0 {
+                self.remaining = self.liveness;
+                Some(socket.recv_multipart(0)?)
+            } else {
+                // A liveness of zero gives up after every silent interval.
+                self.remaining = self.remaining.saturating_sub(1);
+                None
+            };
+            if Instant::now() >= self.send_at {
+                send(socket)?;
+                self.sent();
+            }
+            match frames {
+                Some(frames) => return Ok(Some(frames)),
+                None if self.remaining == 0 => return Ok(None),
+                None => {}
+            }
+        }
+    }
+}
+
+/// The time to wait before reconnecting, doubling after every attempt
+/// up to a maximum.
+pub(crate) struct Backoff {
+    initial: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl Backoff {
+    pub(crate) fn new(initial: Duration, max: Duration) -> Backoff {
+        Backoff {
+            initial,
+            max,
+            current: initial,
+        }
+    }
+
+    /// The time the next call to `wait()` waits.
+    pub(crate) fn current(&self) -> Duration {
+        self.current
+    }
+
+    pub(crate) fn set_initial(&mut self, initial: Duration) {
+        self.initial = initial;
+        self.current = initial;
+    }
+
+    pub(crate) fn set_max(&mut self, max: Duration) {
+        self.max = max;
+    }
+
+    /// Wait before reconnecting, and back off further for the next time.
+    pub(crate) fn wait(&mut self) {
+        thread::sleep(self.current);
+        self.current = (self.current * 2).min(self.max);
+    }
+
+    /// Start over from the initial time, e.g. after hearing from the
+    /// peer.
+    pub(crate) fn reset(&mut self) {
+        self.current = self.initial;
+    }
+}
+
+/// Define the `Request` type a worker receives: the frames of the
+/// request body, along with the `$route` its reply is sent back along.
+macro_rules! worker_request {
+    ($(#[$attr:meta])* $route:ident: $route_type:ty) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct Request {
+            $route: $route_type,
+            body: Vec<Vec<u8>>,
+        }
+
+        impl Request {
+            /// The frames of the request body.
+            pub fn body(&self) -> &[Vec<u8>] {
+                &self.body
+            }
+
+            /// Return the frames of the request body.
+            pub fn into_body(self) -> Vec<Vec<u8>> {
+                self.body
+            }
+        }
+    };
+}
+
+pub(crate) use worker_request;
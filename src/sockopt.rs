@@ -93,6 +93,7 @@ macro_rules! setsockopt_num(
 );
 
 setsockopt_num!(i32);
+setsockopt_num!(u32);
 setsockopt_num!(i64);
 setsockopt_num!(u64);
 
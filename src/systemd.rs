@@ -0,0 +1,64 @@
+//! Support for systemd socket activation.
+
+use std::env;
+use std::io;
+use std::os::unix::io::{FromRawFd, OwnedFd, RawFd};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The first file descriptor passed by systemd, `SD_LISTEN_FDS_START`.
+const LISTEN_FDS_START: RawFd = 3;
+
+/// Take ownership of the sockets passed to this process by systemd
+/// socket activation.
+///
+/// This follows the protocol of `sd_listen_fds(3)`: if `LISTEN_PID`
+/// names this process, `LISTEN_FDS` file descriptors starting at 3 are
+/// returned, in the order of the `ListenStream=` directives of the
+/// socket unit. Otherwise, an empty vector is returned. The returned
+/// descriptors are marked close-on-exec.
+///
+/// The descriptors are handed out at most once per process, so that
+/// no two `OwnedFd`s own the same descriptor: later calls return an
+/// empty vector. The environment is only read, never modified, as
+/// modifying it is not thread-safe; other threads, e.g. libzmq's I/O
+/// threads, may read it concurrently.
+///
+/// Pass the returned descriptors to `Socket::bind_fd()`, along with the
+/// endpoint they are listening on.
+pub fn listen_fds() -> io::Result<Vec<OwnedFd>> {
+    static CLAIMED: AtomicBool = AtomicBool::new(false);
+
+    let (pid, fds) = match (env::var("LISTEN_PID"), env::var("LISTEN_FDS")) {
+        (Ok(pid), Ok(fds)) => (pid, fds),
+        _ => return Ok(Vec::new()),
+    };
+    let invalid = |name| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid {} environment variable", name),
+        )
+    };
+    let pid: u32 = pid.trim().parse().map_err(|_| invalid("LISTEN_PID"))?;
+    if pid != process::id() {
+        return Ok(Vec::new());
+    }
+    let count: RawFd = fds.trim().parse().map_err(|_| invalid("LISTEN_FDS"))?;
+    if !(0..=RawFd::MAX - LISTEN_FDS_START).contains(&count) {
+        return Err(invalid("LISTEN_FDS"));
+    }
+    if CLAIMED.swap(true, Ordering::SeqCst) {
+        return Ok(Vec::new());
+    }
+
+    let mut result = Vec::with_capacity(count as usize);
+    for fd in LISTEN_FDS_START..LISTEN_FDS_START + count {
+        if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        // systemd hands the descriptors over to this process, and
+        // `CLAIMED` makes sure they are only taken once.
+        result.push(unsafe { OwnedFd::from_raw_fd(fd) });
+    }
+    Ok(result)
+}
@@ -0,0 +1,67 @@
+#[macro_use]
+mod common;
+
+use std::sync::mpsc;
+
+use zmq::{Actor, Context};
+
+fn echo(pipe: zmq::Socket) -> zmq::Result<()> {
+    loop {
+        let msg = pipe.recv_multipart(0)?;
+        if msg == [Actor::TERM.as_bytes()] {
+            return Ok(());
+        }
+        pipe.send_multipart(msg, 0)?;
+    }
+}
+
+test!(test_actor_echo, {
+    let ctx = Context::new();
+    let actor = Actor::spawn(&ctx, echo).unwrap();
+    actor.send_multipart(["a", "b"], 0).unwrap();
+    assert_eq!(actor.recv_multipart(0).unwrap(), vec![b"a", b"b"]);
+    actor.terminate().unwrap();
+});
+
+test!(test_actor_drop_sends_term, {
+    let ctx = Context::new();
+    let (tx, rx) = mpsc::channel();
+    let actor = Actor::spawn(&ctx, move |pipe| {
+        let msg = pipe.recv_string(0)?.unwrap();
+        tx.send(msg).unwrap();
+        Ok(())
+    })
+    .unwrap();
+    drop(actor);
+    // Dropping waits for the thread, so the command has been seen.
+    assert_eq!(rx.try_recv().unwrap(), Actor::TERM);
+});
+
+test!(test_actor_error, {
+    let ctx = Context::new();
+    let actor = Actor::spawn(&ctx, |_| Err(zmq::Error::EPROTO)).unwrap();
+    assert_eq!(actor.terminate(), Err(zmq::Error::EPROTO));
+});
+
+test!(test_actor_context_shutdown, {
+    let ctx = Context::new();
+    let actor = Actor::spawn(&ctx, echo).unwrap();
+    ctx.shutdown().unwrap();
+    assert_eq!(actor.terminate(), Err(zmq::Error::ETERM));
+});
+
+test!(test_actor_term_waits_for_full_pipe, {
+    let ctx = Context::new();
+    let (tx, rx) = mpsc::channel();
+    let actor = Actor::spawn(&ctx, move |pipe| {
+        rx.recv().unwrap();
+        while pipe.recv_bytes(0)? != Actor::TERM.as_bytes() {}
+        Ok(())
+    })
+    .unwrap();
+    while actor.send("queued", zmq::DONTWAIT).is_ok() {}
+    // The actor only starts reading once the pipe is full, so the
+    // command can't be sent right away.
+    tx.send(()).unwrap();
+    actor.terminate().unwrap();
+});
@@ -0,0 +1,255 @@
+#![cfg(all(unix, any(feature = "tokio", feature = "async-io")))]
+
+use std::future::Future;
+use std::thread;
+
+use zmq::{AsyncMonitor, AsyncSocket, Context, SocketEvent};
+
+/// Run the same scenario on every enabled backend.
+fn on_each_backend<F, Fut>(test: F)
+where
+    F: Fn(Context) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    #[cfg(feature = "tokio")]
+    tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .unwrap()
+        .block_on(test(Context::new()));
+    #[cfg(feature = "async-io")]
+    async_io::block_on(test(Context::new()));
+}
+
+fn socket(ctx: &Context, socket_type: zmq::SocketType) -> AsyncSocket {
+    AsyncSocket::new(ctx.socket(socket_type).unwrap()).unwrap()
+}
+
+#[test]
+fn send_recv() {
+    on_each_backend(|ctx| async move {
+        let pull = socket(&ctx, zmq::PULL);
+        pull.get_ref().bind("inproc://send-recv").unwrap();
+        let push = socket(&ctx, zmq::PUSH);
+        push.get_ref().connect("inproc://send-recv").unwrap();
+
+        // The receive has to wait for the sender.
+        let (received, sent) = futures_lite::future::zip(pull.recv_bytes(0), async {
+            push.send("hello", 0).await?;
+            push.send_multipart(["a", "b"], 0).await
+        })
+        .await;
+        sent.unwrap();
+        assert_eq!(received.unwrap(), b"hello");
+        assert_eq!(pull.recv_multipart(0).await.unwrap(), vec![b"a", b"b"]);
+    });
+}
+
+#[test]
+fn send_waits_for_hwm() {
+    on_each_backend(|ctx| async move {
+        let pull = socket(&ctx, zmq::PULL);
+        pull.get_ref().set_rcvhwm(1).unwrap();
+        pull.get_ref().bind("inproc://send-hwm").unwrap();
+        let push = socket(&ctx, zmq::PUSH);
+        push.get_ref().set_sndhwm(1).unwrap();
+        push.get_ref().connect("inproc://send-hwm").unwrap();
+
+        let sender = async {
+            for i in 0..100u32 {
+                push.send(&i.to_be_bytes()[..], 0).await.unwrap();
+            }
+        };
+        let receiver = async {
+            for i in 0..100u32 {
+                assert_eq!(pull.recv_bytes(0).await.unwrap(), i.to_be_bytes());
+            }
+        };
+        futures_lite::future::zip(sender, receiver).await;
+    });
+}
+
+#[test]
+fn send_multipart_cancelled() {
+    on_each_backend(|ctx| async move {
+        let pull = socket(&ctx, zmq::PULL);
+        pull.get_ref().set_rcvhwm(1).unwrap();
+        pull.get_ref()
+            .bind("inproc://send-multipart-cancelled")
+            .unwrap();
+        let push = socket(&ctx, zmq::PUSH);
+        push.get_ref().set_sndhwm(1).unwrap();
+        push.get_ref()
+            .connect("inproc://send-multipart-cancelled")
+            .unwrap();
+
+        let mut queued = 0;
+        while push.get_ref().send("queued", zmq::DONTWAIT).is_ok() {
+            queued += 1;
+        }
+        let send = push.send_multipart(["a", "b"], 0);
+        assert!(futures_lite::future::poll_once(send).await.is_none());
+
+        // No part of the cancelled message has been sent.
+        for _ in 0..queued {
+            assert_eq!(pull.recv_multipart(0).await.unwrap(), vec![b"queued"]);
+        }
+        assert_eq!(
+            pull.get_ref().recv_bytes(zmq::DONTWAIT),
+            Err(zmq::Error::EAGAIN)
+        );
+
+        push.send_multipart(["c", "d"], 0).await.unwrap();
+        assert_eq!(pull.recv_multipart(0).await.unwrap(), vec![b"c", b"d"]);
+    });
+}
+
+#[test]
+fn remote_peer() {
+    on_each_backend(|ctx| async move {
+        let rep = ctx.socket(zmq::REP).unwrap();
+        let addr = rep.bind_ephemeral("127.0.0.1").unwrap();
+        let worker = thread::spawn(move || {
+            let msg = rep.recv_string(0).unwrap().unwrap();
+            rep.send(format!("{} world", msg).as_str(), 0).unwrap();
+        });
+
+        let req = socket(&ctx, zmq::REQ);
+        req.get_ref()
+            .connect(zmq::Endpoint::from(addr).as_str())
+            .unwrap();
+        req.send("hello", 0).await.unwrap();
+        assert_eq!(req.recv_string(0).await.unwrap().unwrap(), "hello world");
+        worker.join().unwrap();
+        req.into_inner().close().unwrap();
+    });
+}
+
+#[test]
+fn monitor() {
+    on_each_backend(|ctx| async move {
+        let server = ctx.socket(zmq::PAIR).unwrap();
+        let monitor = AsyncMonitor::new(&ctx, &server, SocketEvent::ALL.to_raw() as i32).unwrap();
+        let addr = server.bind_ephemeral("127.0.0.1").unwrap();
+
+        let event = monitor.next_event().await.unwrap();
+        assert_eq!(event.event(), SocketEvent::LISTENING);
+        assert_eq!(event.endpoint(), format!("tcp://{}", addr));
+
+        let client = ctx.socket(zmq::PAIR).unwrap();
+        client.connect(zmq::Endpoint::from(addr).as_str()).unwrap();
+        let event = monitor.next_event().await.unwrap();
+        assert_eq!(event.event(), SocketEvent::ACCEPTED);
+    });
+}
+
+#[test]
+fn proxy() {
+    on_each_backend(|ctx| async move {
+        let frontend = socket(&ctx, zmq::ROUTER);
+        frontend.get_ref().bind("inproc://proxy-front").unwrap();
+        let backend = socket(&ctx, zmq::DEALER);
+        backend.get_ref().bind("inproc://proxy-back").unwrap();
+
+        let rep = ctx.socket(zmq::REP).unwrap();
+        rep.connect("inproc://proxy-back").unwrap();
+        let worker = thread::spawn(move || {
+            for _ in 0..3 {
+                let msg = rep.recv_bytes(0).unwrap();
+                rep.send(msg, 0).unwrap();
+            }
+        });
+
+        let req = socket(&ctx, zmq::REQ);
+        req.get_ref().connect("inproc://proxy-front").unwrap();
+        let client = async {
+            for i in 0..3u8 {
+                req.send(&[i][..], 0).await.unwrap();
+                assert_eq!(req.recv_bytes(0).await.unwrap(), [i]);
+            }
+        };
+        let proxy = async {
+            zmq::proxy_async(&frontend, &backend).await.unwrap();
+            unreachable!("proxy returned");
+        };
+        futures_lite::future::or(client, proxy).await;
+        worker.join().unwrap();
+    });
+}
+
+#[test]
+fn proxy_capture_and_backpressure() {
+    on_each_backend(|ctx| async move {
+        let frontend = socket(&ctx, zmq::PULL);
+        frontend.get_ref().set_rcvhwm(2).unwrap();
+        frontend.get_ref().bind("inproc://bp-front").unwrap();
+        let backend = socket(&ctx, zmq::PUSH);
+        backend.get_ref().set_sndhwm(2).unwrap();
+        backend.get_ref().bind("inproc://bp-back").unwrap();
+        let capture = socket(&ctx, zmq::PAIR);
+        capture.get_ref().bind("inproc://bp-capture").unwrap();
+
+        let producer = socket(&ctx, zmq::PUSH);
+        producer.get_ref().set_sndhwm(2).unwrap();
+        producer.get_ref().connect("inproc://bp-front").unwrap();
+        let consumer = socket(&ctx, zmq::PULL);
+        consumer.get_ref().set_rcvhwm(2).unwrap();
+        consumer.get_ref().connect("inproc://bp-back").unwrap();
+        let tap = socket(&ctx, zmq::PAIR);
+        tap.get_ref().connect("inproc://bp-capture").unwrap();
+
+        let produce = async {
+            for i in 0..200u32 {
+                producer
+                    .send_multipart([&b"n"[..], &i.to_be_bytes()[..]], 0)
+                    .await
+                    .unwrap();
+            }
+        };
+        // The consumer is the slowest part, but no message is lost.
+        let consume = async {
+            for i in 0..200u32 {
+                let parts = consumer.recv_multipart(0).await.unwrap();
+                assert_eq!(parts, vec![b"n".to_vec(), i.to_be_bytes().to_vec()]);
+            }
+        };
+        let proxy = async {
+            zmq::proxy_async(&frontend, &backend)
+                .capture(&capture)
+                .await
+                .unwrap();
+        };
+        let work = futures_lite::future::zip(produce, consume);
+        futures_lite::future::or(work, async {
+            proxy.await;
+            unreachable!("proxy returned");
+        })
+        .await;
+
+        // Captured messages may have been dropped, but arrive whole.
+        let parts = tap.recv_multipart(0).await.unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0], b"n");
+    });
+}
+
+#[test]
+fn proxy_drop() {
+    on_each_backend(|ctx| async move {
+        let frontend = socket(&ctx, zmq::PULL);
+        frontend.get_ref().bind("inproc://drop-front").unwrap();
+        let backend = socket(&ctx, zmq::PUSH);
+        backend.get_ref().bind("inproc://drop-back").unwrap();
+
+        // Without a consumer, the proxy can't forward anything.
+        let producer = ctx.socket(zmq::PUSH).unwrap();
+        producer.connect("inproc://drop-front").unwrap();
+        producer.send("queued", 0).unwrap();
+        let proxy = zmq::proxy_async(&frontend, &backend);
+        assert!(futures_lite::future::poll_once(proxy).await.is_none());
+
+        // The message stays queued on the frontend after dropping the
+        // proxy.
+        assert_eq!(frontend.recv_bytes(0).await.unwrap(), b"queued");
+    });
+}
@@ -0,0 +1,91 @@
+#[macro_use]
+mod common;
+
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::time::Duration;
+
+use zmq::{Beacon, Context};
+
+const LOOPBACK_BROADCAST: Ipv4Addr = Ipv4Addr::new(127, 255, 255, 255);
+const INTERVAL: Duration = Duration::from_millis(20);
+
+/// Pick a UDP port that is currently free, so that concurrent test runs
+/// don't hear each other's beacons.
+fn free_port() -> u16 {
+    let udp = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+    udp.local_addr().unwrap().port()
+}
+
+fn beacon(ctx: &Context, port: u16) -> Beacon {
+    Beacon::with_broadcast_address(ctx, port, LOOPBACK_BROADCAST).unwrap()
+}
+
+test!(test_beacon_discovers_peer, {
+    let ctx = Context::new();
+    let port = free_port();
+    let a = beacon(&ctx, port);
+    let b = beacon(&ctx, port);
+    a.publish(b"peer-a", INTERVAL).unwrap();
+    b.publish(b"peer-b", INTERVAL).unwrap();
+    a.subscribe(b"peer-").unwrap();
+
+    // Beacons also receive their own broadcasts.
+    let mut seen = Vec::new();
+    while !(seen.contains(&b"peer-a".to_vec()) && seen.contains(&b"peer-b".to_vec())) {
+        let (peer, payload) = a.recv(0).unwrap();
+        assert_eq!(peer, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        seen.push(payload);
+    }
+    a.terminate().unwrap();
+    b.terminate().unwrap();
+});
+
+test!(test_beacon_filters_by_prefix, {
+    let ctx = Context::new();
+    let port = free_port();
+    let a = beacon(&ctx, port);
+    let b = beacon(&ctx, port);
+    a.subscribe(b"other-").unwrap();
+    b.publish(b"peer-b", INTERVAL).unwrap();
+    assert_eq!(a.get_ref().poll(zmq::POLLIN, 200).unwrap(), 0);
+
+    // An empty prefix matches everything, until unsubscribing.
+    b.subscribe(b"").unwrap();
+    assert_eq!(b.recv(0).unwrap().1, b"peer-b");
+    b.unsubscribe().unwrap();
+    while b.get_ref().poll(zmq::POLLIN, 200).unwrap() != 0 {
+        b.recv(0).unwrap();
+    }
+});
+
+test!(test_beacon_silence, {
+    let ctx = Context::new();
+    let a = beacon(&ctx, free_port());
+    a.subscribe(b"").unwrap();
+    a.publish(b"ping", INTERVAL).unwrap();
+    assert_eq!(a.recv(0).unwrap().1, b"ping");
+    a.silence().unwrap();
+    // Drain the beacons that were sent before silencing.
+    while a.get_ref().poll(zmq::POLLIN, 200).unwrap() != 0 {
+        a.recv(0).unwrap();
+    }
+});
+
+test!(test_beacon_invalid_publish, {
+    let ctx = Context::new();
+    let a = beacon(&ctx, free_port());
+    assert_eq!(
+        a.publish(&[0; zmq::BEACON_MAX + 1], INTERVAL),
+        Err(zmq::Error::EINVAL)
+    );
+    assert_eq!(
+        a.publish(b"ping", Duration::from_millis(0)),
+        Err(zmq::Error::EINVAL)
+    );
+    // Sub-millisecond intervals would round down to broadcasting in a
+    // busy loop.
+    assert_eq!(
+        a.publish(b"ping", Duration::from_micros(500)),
+        Err(zmq::Error::EINVAL)
+    );
+});
@@ -0,0 +1,96 @@
+#[macro_use]
+mod common;
+
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use zmq::bstar::{BinaryStar, State};
+use zmq::pirate::Client;
+use zmq::Context;
+
+const HEARTBEAT: Duration = Duration::from_millis(50);
+
+/// Run an echo server on a background thread until its context is shut
+/// down, reporting its state changes on `events`.
+fn start_server(
+    ctx: &Context,
+    primary: bool,
+    ports: (u16, u16, u16),
+    events: mpsc::Sender<State>,
+) -> thread::JoinHandle<zmq::Error> {
+    let (local, remote, voter) = ports;
+    let ctx = ctx.clone();
+    thread::spawn(move || {
+        let local = format!("tcp://127.0.0.1:{}", local);
+        let remote = format!("tcp://127.0.0.1:{}", remote);
+        let mut bstar = BinaryStar::new(&ctx, primary, &local, &remote).unwrap();
+        bstar.set_heartbeat_interval(HEARTBEAT);
+        let voter = format!("tcp://127.0.0.1:{}", voter);
+        bstar
+            .voter(&voter, zmq::ROUTER, |socket| {
+                let request = socket.recv_multipart(0)?;
+                socket.send_multipart(request, 0)
+            })
+            .unwrap();
+        let passive = events.clone();
+        bstar.on_active(move || events.send(State::Active).unwrap());
+        bstar.on_passive(move || passive.send(State::Passive).unwrap());
+        bstar.run().unwrap_err()
+    })
+}
+
+fn connect_client(ctx: &Context, port: u16) -> Client {
+    let mut client = Client::new(ctx, &format!("tcp://127.0.0.1:{}", port)).unwrap();
+    client.set_timeout(HEARTBEAT * 2);
+    client.set_retries(10);
+    client
+}
+
+test!(test_failover, {
+    let primary_ctx = Context::new();
+    let (primary_events, primary_states) = mpsc::channel();
+    let primary = start_server(&primary_ctx, true, (31881, 31882, 31883), primary_events);
+    let backup_ctx = Context::new();
+    let (backup_events, backup_states) = mpsc::channel();
+    let _backup = start_server(&backup_ctx, false, (31882, 31881, 31884), backup_events);
+    assert_eq!(primary_states.recv().unwrap(), State::Active);
+    assert_eq!(backup_states.recv().unwrap(), State::Passive);
+
+    let ctx = Context::new();
+    let mut client = connect_client(&ctx, 31883);
+    assert_eq!(client.send(&["one"]).unwrap(), vec![b"one"]);
+
+    // Once the primary is gone, the backup takes over when a client
+    // turns to it.
+    primary_ctx.shutdown().unwrap();
+    assert_eq!(primary.join().unwrap(), zmq::Error::ETERM);
+    let mut client = connect_client(&ctx, 31884);
+    assert_eq!(client.send(&["two"]).unwrap(), vec![b"two"]);
+    assert_eq!(backup_states.recv().unwrap(), State::Active);
+
+    ctx.shutdown().unwrap();
+    backup_ctx.shutdown().unwrap();
+});
+
+test!(test_server_without_peer, {
+    let ctx = Context::new();
+
+    // A primary serves clients once its peer has been silent for long
+    // enough.
+    let (events, states) = mpsc::channel();
+    let _primary = start_server(&ctx, true, (31885, 31886, 31887), events);
+    let mut client = connect_client(&ctx, 31887);
+    assert_eq!(client.send(&["hello"]).unwrap(), vec![b"hello"]);
+    assert_eq!(states.recv().unwrap(), State::Active);
+
+    // A backup never does.
+    let (events, states) = mpsc::channel();
+    let _backup = start_server(&ctx, false, (31888, 31889, 31890), events);
+    let mut client = connect_client(&ctx, 31890);
+    client.set_retries(3);
+    assert_eq!(client.send(&["hello"]), Err(zmq::Error::ETIMEDOUT));
+    assert!(states.try_recv().is_err());
+
+    ctx.shutdown().unwrap();
+});
@@ -0,0 +1,176 @@
+#[macro_use]
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+use zmq::clone::{Client, KvMessage, Server};
+use zmq::Context;
+
+const HEARTBEAT: Duration = Duration::from_millis(50);
+
+fn endpoints(name: &str) -> [String; 3] {
+    [
+        format!("inproc://clone-{}-snapshot", name),
+        format!("inproc://clone-{}-publisher", name),
+        format!("inproc://clone-{}-collector", name),
+    ]
+}
+
+fn start_server(ctx: &Context, name: &str) -> thread::JoinHandle<zmq::Error> {
+    let [snapshot, publisher, collector] = endpoints(name);
+    let mut server = Server::new(ctx).unwrap();
+    server.set_heartbeat_interval(HEARTBEAT);
+    server.bind_snapshot(&snapshot).unwrap();
+    server.bind_publisher(&publisher).unwrap();
+    server.bind_collector(&collector).unwrap();
+    thread::spawn(move || server.run().unwrap_err())
+}
+
+fn client(ctx: &Context, name: &str, subtree: &str) -> Client {
+    let [snapshot, publisher, collector] = endpoints(name);
+    Client::new(ctx, &snapshot, &publisher, &collector, subtree).unwrap()
+}
+
+test!(test_replication, {
+    let ctx = Context::new();
+    let server = start_server(&ctx, "replication");
+
+    let mut first = client(&ctx, "replication", "");
+    first.set("key", "one", None).unwrap();
+    let update = first.recv().unwrap();
+    assert_eq!((update.key(), update.sequence()), ("key", 1));
+    assert_eq!(first.get("key"), Some(&b"one"[..]));
+
+    // A new client starts from a snapshot.
+    let mut second = client(&ctx, "replication", "");
+    assert_eq!(second.get("key"), Some(&b"one"[..]));
+    assert_eq!(second.sequence(), 1);
+
+    second.set("key", "", None).unwrap();
+    for client in [&mut first, &mut second].iter_mut() {
+        let update = client.recv().unwrap();
+        assert!(update.is_delete());
+        assert_eq!(client.get("key"), None);
+        assert_eq!(client.sequence(), 2);
+    }
+
+    ctx.shutdown().unwrap();
+    assert_eq!(server.join().unwrap(), zmq::Error::ETERM);
+});
+
+test!(test_subtree, {
+    let ctx = Context::new();
+    let _server = start_server(&ctx, "subtree");
+
+    let mut whole = client(&ctx, "subtree", "");
+    whole.set("a/one", "1", None).unwrap();
+    whole.set("b/two", "2", None).unwrap();
+    whole.recv().unwrap();
+    whole.recv().unwrap();
+
+    let mut subtree = client(&ctx, "subtree", "a/");
+    assert_eq!(subtree.get("a/one"), Some(&b"1"[..]));
+    assert_eq!(subtree.get("b/two"), None);
+
+    whole.set("b/three", "3", None).unwrap();
+    whole.set("a/four", "4", None).unwrap();
+    let update = subtree.recv().unwrap();
+    assert_eq!((update.key(), update.sequence()), ("a/four", 4));
+
+    ctx.shutdown().unwrap();
+});
+
+test!(test_ttl, {
+    let ctx = Context::new();
+    let _server = start_server(&ctx, "ttl");
+
+    let mut client = client(&ctx, "ttl", "");
+    client
+        .set("key", "value", Some(Duration::from_millis(100)))
+        .unwrap();
+    let update = client.recv().unwrap();
+    assert_eq!(update.ttl(), Some(Duration::from_millis(100)));
+    assert_eq!(client.get("key"), Some(&b"value"[..]));
+
+    // The server deletes the key once it has expired.
+    let update = client.recv().unwrap();
+    assert_eq!((update.key(), update.is_delete()), ("key", true));
+    assert_eq!(client.get("key"), None);
+
+    ctx.shutdown().unwrap();
+});
+
+/// Answer a snapshot request from a `Client` with `updates`.
+fn send_snapshot(socket: &zmq::Socket, updates: &[KvMessage], sequence: u64) {
+    let request = socket.recv_multipart(0).unwrap();
+    assert_eq!(&request[1..], [&b"ICANHAZ?"[..], b""]);
+    let mut kthxbai = KvMessage::new("KTHXBAI", "");
+    kthxbai.set_sequence(sequence);
+    for update in updates.iter().chain(Some(&kthxbai)) {
+        socket.send(&request[0], zmq::SNDMORE).unwrap();
+        update.send(socket, 0).unwrap();
+    }
+}
+
+fn update(key: &str, body: &str, sequence: u64) -> KvMessage {
+    let mut update = KvMessage::new(key, body);
+    update.set_sequence(sequence);
+    update
+}
+
+test!(test_gap_detection, {
+    let ctx = Context::new();
+    let [snapshot, publisher, collector] = endpoints("gap");
+    let snapshot_socket = ctx.socket(zmq::ROUTER).unwrap();
+    snapshot_socket.bind(&snapshot).unwrap();
+    let publisher_socket = ctx.socket(zmq::PUB).unwrap();
+    publisher_socket.bind(&publisher).unwrap();
+
+    // A server that loses the second update.
+    let server = thread::spawn(move || {
+        while snapshot_socket.poll(zmq::POLLIN, 10).unwrap() == 0 {
+            KvMessage::new("HUGZ", "")
+                .send(&publisher_socket, 0)
+                .unwrap();
+        }
+        send_snapshot(&snapshot_socket, &[], 0);
+        update("a", "1", 1).send(&publisher_socket, 0).unwrap();
+        update("c", "3", 3).send(&publisher_socket, 0).unwrap();
+        let updates = [
+            update("a", "1", 1),
+            update("b", "2", 2),
+            update("c", "3", 3),
+        ];
+        send_snapshot(&snapshot_socket, &updates, 3);
+        update("d", "4", 4).send(&publisher_socket, 0).unwrap();
+    });
+
+    let mut client = Client::new(&ctx, &snapshot, &publisher, &collector, "").unwrap();
+    assert_eq!(client.recv().unwrap().key(), "a");
+    assert_eq!(client.recv().unwrap().key(), "d");
+    assert_eq!(client.get("b"), Some(&b"2"[..]));
+    assert_eq!(client.sequence(), 4);
+    server.join().unwrap();
+
+    ctx.shutdown().unwrap();
+});
+
+test!(test_kv_message, {
+    let ctx = Context::new();
+    let (sender, receiver) = ctx.pipe().unwrap();
+
+    let mut message = KvMessage::new("key", "value");
+    message.set_sequence(42);
+    message.set_uuid(&[1; 16][..]);
+    message.set_property("origin", "test");
+    message.set_ttl(Duration::from_millis(1500));
+    message.send(&sender, 0).unwrap();
+    let received = KvMessage::recv(&receiver, 0).unwrap();
+    assert_eq!(received, message);
+    assert_eq!(received.property("origin"), Some("test"));
+    assert_eq!(received.ttl(), Some(Duration::from_millis(1500)));
+
+    sender.send_multipart(["key", "value"], 0).unwrap();
+    assert_eq!(KvMessage::recv(&receiver, 0), Err(zmq::Error::EPROTO));
+});
@@ -1,3 +1,6 @@
+use std::thread;
+use std::time::Duration;
+
 #[test]
 fn context_io_threads() {
     let ctx = zmq::Context::new();
@@ -15,3 +18,212 @@ fn context_io_threads() {
 
     assert!(ctx.set_io_threads(-1).is_err());
 }
+
+#[test]
+fn context_max_sockets() {
+    let ctx = zmq::Context::new();
+
+    assert_eq!(
+        ctx.get_max_sockets().unwrap(),
+        zmq_sys::ZMQ_MAX_SOCKETS_DFLT as i32
+    );
+    assert!(ctx.get_socket_limit().unwrap() >= ctx.get_max_sockets().unwrap());
+
+    ctx.set_max_sockets(42).unwrap();
+    assert_eq!(ctx.get_max_sockets().unwrap(), 42);
+
+    assert!(ctx.set_max_sockets(0).is_err());
+}
+
+#[test]
+fn context_thread_options() {
+    let ctx = zmq::Context::new();
+
+    ctx.set_thread_name_prefix(7).unwrap();
+    assert_eq!(ctx.get_thread_name_prefix().unwrap(), 7);
+
+    ctx.add_thread_affinity_cpu(0).unwrap();
+    ctx.remove_thread_affinity_cpu(0).unwrap();
+    assert!(ctx.remove_thread_affinity_cpu(0).is_err());
+}
+
+#[test]
+fn context_misc_options() {
+    let ctx = zmq::Context::new();
+
+    ctx.set_max_msgsz(4096).unwrap();
+    assert_eq!(ctx.get_max_msgsz().unwrap(), 4096);
+
+    assert_eq!(
+        ctx.get_msg_t_size().unwrap() as usize,
+        std::mem::size_of::<zmq_sys::zmq_msg_t>()
+    );
+
+    assert!(ctx.is_blocky().unwrap());
+    ctx.set_blocky(false).unwrap();
+    assert!(!ctx.is_blocky().unwrap());
+}
+
+#[test]
+fn context_builder() {
+    let ctx = zmq::Context::builder()
+        .io_threads(2)
+        .max_sockets(64)
+        .thread_affinity_cpu(0)
+        .thread_name_prefix(3)
+        .max_msgsz(1024)
+        .blocky(false)
+        .build()
+        .unwrap();
+
+    assert_eq!(ctx.get_io_threads().unwrap(), 2);
+    assert_eq!(ctx.get_max_sockets().unwrap(), 64);
+    assert_eq!(ctx.get_thread_name_prefix().unwrap(), 3);
+    assert_eq!(ctx.get_max_msgsz().unwrap(), 1024);
+    assert!(!ctx.is_blocky().unwrap());
+
+    let sock = ctx.socket(zmq::PAIR).unwrap();
+    sock.bind("inproc://context-builder").unwrap();
+
+    assert!(zmq::Context::builder().io_threads(-1).build().is_err());
+}
+
+#[test]
+fn context_shutdown() {
+    let ctx = zmq::Context::new();
+    let sock = ctx.socket(zmq::PULL).unwrap();
+    sock.bind("inproc://context-shutdown").unwrap();
+
+    ctx.shutdown().unwrap();
+
+    assert_eq!(sock.recv_bytes(0).unwrap_err(), zmq::Error::ETERM);
+    assert_eq!(ctx.socket(zmq::PUSH).err(), Some(zmq::Error::ETERM));
+}
+
+#[test]
+fn context_shutdown_handle() {
+    let ctx = zmq::Context::new();
+    let handle = ctx.shutdown_handle();
+
+    let worker = {
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let sock = ctx.socket(zmq::PULL).unwrap();
+            sock.bind("inproc://context-shutdown-handle").unwrap();
+            sock.recv_bytes(0)
+        })
+    };
+    drop(ctx);
+
+    // Give the worker a chance to block in `recv`.
+    thread::sleep(Duration::from_millis(50));
+    handle.clone().shutdown().unwrap();
+    assert_eq!(worker.join().unwrap().unwrap_err(), zmq::Error::ETERM);
+
+    // The context is gone by now, so shutting down again is a no-op.
+    handle.shutdown().unwrap();
+}
+
+#[test]
+fn context_destroyed() {
+    let mut ctx = zmq::Context::new();
+    let clone = ctx.clone();
+    let handle = ctx.shutdown_handle();
+    ctx.destroy().unwrap();
+
+    // The clone still references the freed context, which must not be
+    // passed to libzmq anymore.
+    assert_eq!(clone.get_io_threads(), Err(zmq::Error::ETERM));
+    assert_eq!(clone.set_io_threads(2), Err(zmq::Error::ETERM));
+    assert_eq!(clone.socket(zmq::PAIR).err(), Some(zmq::Error::ETERM));
+    clone.shutdown().unwrap();
+    handle.shutdown().unwrap();
+    ctx.destroy().unwrap();
+}
+
+#[test]
+fn context_destroy_concurrently() {
+    let ctx = zmq::Context::new();
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let mut ctx = ctx.clone();
+            thread::spawn(move || ctx.destroy())
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap().unwrap();
+    }
+    assert_eq!(ctx.get_io_threads(), Err(zmq::Error::ETERM));
+}
+
+#[test]
+fn context_socket_while_destroying() {
+    let ctx = zmq::Context::new();
+    let socket = ctx.socket(zmq::PAIR).unwrap();
+    let destroyer = thread::spawn({
+        let mut ctx = ctx.clone();
+        move || ctx.destroy()
+    });
+    // Wait for the destroyer to block on the open socket.
+    while ctx.get_io_threads().is_ok() {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let creator = thread::spawn({
+        let ctx = ctx.clone();
+        move || ctx.socket(zmq::PAIR).err()
+    });
+    assert_eq!(creator.join().unwrap(), Some(zmq::Error::ETERM));
+    drop(socket);
+    destroyer.join().unwrap().unwrap();
+}
+
+#[test]
+fn context_pipe() {
+    let ctx = zmq::Context::new();
+    let (a, b) = ctx.pipe().unwrap();
+    let (c, d) = ctx.pipe().unwrap();
+    assert_eq!(a.get_socket_type().unwrap(), zmq::PAIR);
+    assert_eq!(b.get_socket_type().unwrap(), zmq::PAIR);
+
+    a.send("to b", 0).unwrap();
+    b.send("to a", 0).unwrap();
+    c.send("to d", 0).unwrap();
+    assert_eq!(b.recv_bytes(0).unwrap(), b"to b");
+    assert_eq!(a.recv_bytes(0).unwrap(), b"to a");
+    assert_eq!(d.recv_bytes(0).unwrap(), b"to d");
+}
+
+#[test]
+fn context_pipe_with_hwm() {
+    let ctx = zmq::Context::new();
+    let (a, b) = ctx.pipe_with_hwm(1).unwrap();
+    for socket in &[&a, &b] {
+        assert_eq!(socket.get_sndhwm().unwrap(), 1);
+        assert_eq!(socket.get_rcvhwm().unwrap(), 1);
+    }
+    // With inproc, the send and receive high-water marks add up.
+    a.send("1", zmq::DONTWAIT).unwrap();
+    a.send("2", zmq::DONTWAIT).unwrap();
+    assert_eq!(a.send("3", zmq::DONTWAIT), Err(zmq::Error::EAGAIN));
+    assert_eq!(b.recv_bytes(0).unwrap(), b"1");
+}
+
+#[test]
+fn context_socket_pair() {
+    let ctx = zmq::Context::new();
+    let (router, dealer) = ctx.socket_pair(zmq::ROUTER, zmq::DEALER).unwrap();
+    assert_eq!(router.get_socket_type().unwrap(), zmq::ROUTER);
+    assert_eq!(dealer.get_socket_type().unwrap(), zmq::DEALER);
+
+    dealer.send("request", 0).unwrap();
+    let mut parts = router.recv_multipart(0).unwrap();
+    assert_eq!(parts.pop().unwrap(), b"request");
+    parts.push(b"reply".to_vec());
+    router.send_multipart(parts, 0).unwrap();
+    assert_eq!(dealer.recv_bytes(0).unwrap(), b"reply");
+
+    let (pull, push) = ctx.socket_pair(zmq::PULL, zmq::PUSH).unwrap();
+    push.send("task", 0).unwrap();
+    assert_eq!(pull.recv_bytes(0).unwrap(), b"task");
+}
@@ -0,0 +1,210 @@
+use std::convert::TryFrom;
+use std::net::SocketAddr;
+
+use zmq::{Endpoint, Error, Port, Transport};
+
+#[test]
+fn parse_tcp() {
+    let ep = Endpoint::parse("tcp://127.0.0.1:5555").unwrap();
+    assert_eq!(ep.transport(), Transport::Tcp);
+    assert_eq!(ep.address(), "127.0.0.1:5555");
+    assert_eq!(ep.host(), Some("127.0.0.1"));
+    assert_eq!(ep.port(), Some(Port::Number(5555)));
+    assert_eq!(ep.source(), None);
+    assert!(!ep.is_wildcard());
+    assert_eq!(ep.to_string(), "tcp://127.0.0.1:5555");
+
+    let ep: Endpoint = "tcp://*:*".parse().unwrap();
+    assert_eq!(ep.host(), Some("*"));
+    assert_eq!(ep.port(), Some(Port::Wildcard));
+    assert!(ep.is_wildcard());
+
+    // Endpoints are passed on unchanged, so CZMQ's `!` is not
+    // understood, just like by libzmq.
+    assert!(Endpoint::parse("tcp://eth0:!").is_err());
+}
+
+#[test]
+fn parse_tcp_ipv6_and_source() {
+    let ep = Endpoint::parse("tcp://[::1]:5555").unwrap();
+    assert_eq!(ep.host(), Some("::1"));
+    assert_eq!(ep.port(), Some(Port::Number(5555)));
+
+    let ep = Endpoint::parse("tcp://192.168.1.17:5555;[fe80::1%eth0]:*").unwrap();
+    assert_eq!(ep.source(), Some("192.168.1.17:5555"));
+    assert_eq!(ep.host(), Some("fe80::1%eth0"));
+    assert_eq!(ep.port(), Some(Port::Wildcard));
+
+    // libzmq also accepts IPv6 addresses without brackets.
+    let ep = Endpoint::parse("tcp://::1:5555").unwrap();
+    assert_eq!(ep.host(), Some("::1"));
+    assert_eq!(ep.port(), Some(Port::Number(5555)));
+    assert_eq!(ep.socket_addr(), Some("[::1]:5555".parse().unwrap()));
+
+    assert!(Endpoint::parse("tcp://[::1:5555").is_err());
+    assert!(Endpoint::parse("tcp://[not-ipv6]:5555").is_err());
+}
+
+#[test]
+fn parse_other_transports() {
+    let ep = Endpoint::parse("ipc:///tmp/socket").unwrap();
+    assert_eq!(ep.transport(), Transport::Ipc);
+    assert_eq!(ep.address(), "/tmp/socket");
+    assert_eq!(ep.host(), None);
+    assert!(Endpoint::parse("ipc://*").unwrap().is_wildcard());
+    assert!(Endpoint::parse("ipc://@abstract").is_ok());
+
+    let ep = Endpoint::parse("inproc://name").unwrap();
+    assert_eq!(ep.transport(), Transport::Inproc);
+    assert_eq!(ep.port(), None);
+
+    let ep = Endpoint::parse("udp://*:5555").unwrap();
+    assert_eq!(ep.transport(), Transport::Udp);
+
+    let ep = Endpoint::parse("ws://localhost:8080/zmq").unwrap();
+    assert_eq!(ep.transport(), Transport::Ws);
+    assert_eq!(ep.host(), Some("localhost"));
+    assert_eq!(ep.port(), Some(Port::Number(8080)));
+    assert_eq!(ep.path(), Some("/zmq"));
+
+    let ep = Endpoint::parse("epgm://eth0;239.192.1.1:5555").unwrap();
+    assert_eq!(ep.source(), Some("eth0"));
+    assert_eq!(ep.host(), Some("239.192.1.1"));
+    assert!(Endpoint::parse("pgm://239.192.1.1:5555").is_err());
+
+    assert!(Endpoint::parse("tipc://{5560,0,0}").is_ok());
+}
+
+#[test]
+fn parse_errors() {
+    for endpoint in &[
+        "",
+        "tcp:/127.0.0.1:5555",
+        "tcp://",
+        "tcp://127.0.0.1",
+        "tcp://127.0.0.1:",
+        "tcp://127.0.0.1:65536",
+        "tcp://127.0.0.1:port",
+        "tcp://:5555",
+        "inproc://",
+        "ipc://@",
+        "inproc://nul\0byte",
+    ] {
+        let err = Endpoint::parse(endpoint).unwrap_err();
+        assert_eq!(err.endpoint(), *endpoint);
+        assert_eq!(Error::from(err), Error::EINVAL, "{:?}", endpoint);
+    }
+
+    let err = Endpoint::parse("bogus://endpoint").unwrap_err();
+    assert_eq!(err.reason(), "unknown transport");
+    assert_eq!(Error::from(err), Error::EPROTONOSUPPORT);
+}
+
+#[test]
+fn conversions() {
+    let s = String::from("inproc://conversions");
+    let from_str = Endpoint::try_from(s.as_str()).unwrap();
+    assert_eq!(Endpoint::try_from(&s).unwrap(), from_str);
+    assert_eq!(Endpoint::try_from(s).unwrap(), from_str);
+    assert_eq!(from_str.as_ref() as &str, "inproc://conversions");
+}
+
+#[test]
+fn socket_rejects_invalid_endpoints() {
+    let ctx = zmq::Context::new();
+    let socket = ctx.socket(zmq::PAIR).unwrap();
+    assert_eq!(socket.bind("inproc://nul\0byte"), Err(Error::EINVAL));
+    assert_eq!(socket.connect("tcp://nul\0byte:5555"), Err(Error::EINVAL));
+    assert_eq!(socket.unbind("\0"), Err(Error::EINVAL));
+    assert_eq!(socket.disconnect("tcp://[::1:5555"), Err(Error::EINVAL));
+}
+
+#[test]
+fn socket_accepts_endpoints() {
+    let ctx = zmq::Context::new();
+    let server = ctx.socket(zmq::PAIR).unwrap();
+    let client = ctx.socket(zmq::PAIR).unwrap();
+
+    let endpoint = Endpoint::parse("tcp://127.0.0.1:*").unwrap();
+    server.bind(endpoint.as_str()).unwrap();
+    let last = server.get_last_endpoint().unwrap().unwrap();
+    let bound = Endpoint::parse(&last).unwrap();
+    assert!(matches!(bound.port(), Some(Port::Number(port)) if port != 0));
+
+    client.connect(bound.as_str()).unwrap();
+
+    // Endpoints are taken as `&str`, so `as_ref()` on a `String` infers.
+    let inproc = String::from("inproc://socket-accepts-endpoints");
+    server.bind(inproc.as_ref()).unwrap();
+    client.send("hello", 0).unwrap();
+    assert_eq!(server.recv_string(0).unwrap().unwrap(), "hello");
+}
+
+#[test]
+fn connectable() {
+    let ep = Endpoint::parse("tcp://*:5555").unwrap().connectable();
+    assert_eq!(ep.as_str(), "tcp://127.0.0.1:5555");
+    let ep = Endpoint::parse("ws://[::]:80/path").unwrap().connectable();
+    assert_eq!(ep.as_str(), "ws://[::1]:80/path");
+    let ep = Endpoint::parse("tcp://:::5555").unwrap().connectable();
+    assert_eq!(ep.as_str(), "tcp://[::1]:5555");
+    let ep = Endpoint::parse("tcp://10.0.0.1:5555").unwrap();
+    assert_eq!(ep.connectable(), ep);
+
+    let addr: SocketAddr = "[::1]:5555".parse().unwrap();
+    let ep = Endpoint::from(addr);
+    assert_eq!(ep.as_str(), "tcp://[::1]:5555");
+    assert_eq!(ep.socket_addr(), Some(addr));
+}
+
+#[test]
+fn bind_ephemeral() {
+    let ctx = zmq::Context::new();
+    let server = ctx.socket(zmq::PAIR).unwrap();
+    let addr = server.bind_ephemeral("0.0.0.0").unwrap();
+    assert!(addr.ip().is_unspecified());
+    assert_ne!(addr.port(), 0);
+
+    let loopback = server.bind_ephemeral("127.0.0.1").unwrap();
+    server.bind("inproc://bind-ephemeral").unwrap();
+    assert_eq!(server.local_endpoints(), vec![addr, loopback]);
+
+    let client = ctx.socket(zmq::PAIR).unwrap();
+    client
+        .connect(Endpoint::from(addr).connectable().as_str())
+        .unwrap();
+    client.send("hello", 0).unwrap();
+    assert_eq!(server.recv_string(0).unwrap().unwrap(), "hello");
+
+    server.unbind(Endpoint::from(addr).as_str()).unwrap();
+    assert_eq!(server.local_endpoints(), vec![loopback]);
+}
+
+#[test]
+fn track_endpoints() {
+    let ctx = zmq::Context::new();
+    let server = ctx.socket(zmq::ROUTER).unwrap();
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    server.bind("inproc://track-endpoints").unwrap();
+    let bound = server.bound_endpoints();
+    assert_eq!(bound.len(), 2);
+    assert!(matches!(bound[0].port(), Some(Port::Number(port)) if port != 0));
+    assert_eq!(bound[1].as_str(), "inproc://track-endpoints");
+
+    let client = ctx.socket(zmq::DEALER).unwrap();
+    client.connect(bound[0].as_str()).unwrap();
+    client.connect("inproc://track-endpoints").unwrap();
+    assert_eq!(client.connected_endpoints(), bound);
+
+    client.disconnect("inproc://track-endpoints").unwrap();
+    assert_eq!(client.connected_endpoints(), vec![bound[0].clone()]);
+
+    // Wildcard binds are unbound using the resolved endpoint.
+    server.unbind_all().unwrap();
+    assert!(server.bound_endpoints().is_empty());
+    assert_eq!(server.unbind(bound[1].as_str()), Err(Error::ENOENT));
+
+    client.disconnect_all().unwrap();
+    assert!(client.connected_endpoints().is_empty());
+    assert_eq!(client.disconnect(bound[0].as_str()), Err(Error::ENOENT));
+}
@@ -6,3 +6,62 @@ fn from_raw_eintr() {
     let error = Error::from_raw(errno::EINTR);
     assert_eq!(error, Error::EINTR);
 }
+
+#[test]
+fn from_raw_unknown() {
+    let error = Error::from_raw(-4242);
+    assert_eq!(error, Error::Unknown(-4242));
+    assert_eq!(error.to_raw(), -4242);
+    // Formatting must not panic, regardless of what libzmq reports.
+    let _ = format!("{} {:?}", error, error);
+}
+
+#[test]
+fn from_raw_alt_errno() {
+    assert_eq!(Error::from_raw(errno::ECONNRESET_ALT), Error::ECONNRESET);
+    assert_eq!(Error::from_raw(errno::ETIMEDOUT_ALT), Error::ETIMEDOUT);
+    assert_eq!(Error::from_raw(errno::ETIMEDOUT), Error::ETIMEDOUT);
+}
+
+#[test]
+fn into_io_error_kinds() {
+    use std::io::{self, ErrorKind};
+
+    let kind = |e: Error| io::Error::from(e).kind();
+    assert_eq!(kind(Error::ECONNRESET), ErrorKind::ConnectionReset);
+    assert_eq!(kind(Error::ETIMEDOUT), ErrorKind::TimedOut);
+    assert_eq!(kind(Error::ECONNREFUSED), ErrorKind::ConnectionRefused);
+    assert_eq!(kind(Error::ENOTSUP), ErrorKind::Unsupported);
+    assert_eq!(kind(Error::ETERM), ErrorKind::Other);
+    assert_eq!(kind(Error::Unknown(-4242)), ErrorKind::Other);
+}
+
+#[test]
+fn socket_event_unknown() {
+    let event = SocketEvent::from_raw(0x8000);
+    assert_eq!(event, SocketEvent::Unknown);
+    assert_eq!(event.to_raw(), 0);
+
+    let header = [&0x8000u16.to_ne_bytes()[..], &7u32.to_ne_bytes()].concat();
+    let event = zmq::MonitorEvent::from_frames(&[&header[..], b"inproc://monitored"]).unwrap();
+    assert_eq!(event.event(), SocketEvent::Unknown);
+    assert_eq!(event.raw_event(), 0x8000);
+
+    let event = SocketEvent::from(zmq_sys::ZMQ_EVENT_CLOSED as u16);
+    assert_eq!(event, SocketEvent::CLOSED);
+    assert_eq!(event.to_raw(), zmq_sys::ZMQ_EVENT_CLOSED as u16);
+    assert_eq!(SocketEvent::ALL as i32, zmq_sys::ZMQ_EVENT_ALL as i32);
+}
+
+#[test]
+fn socket_type_try_from() {
+    use std::convert::TryFrom;
+
+    assert_eq!(SocketType::try_from(zmq_sys::ZMQ_ROUTER as i32), Ok(ROUTER));
+    assert_eq!(SocketType::try_from(12345), Err(Error::EINVAL));
+    assert_eq!(
+        Mechanism::try_from(zmq_sys::ZMQ_CURVE as i32),
+        Ok(Mechanism::ZMQ_CURVE)
+    );
+    assert_eq!(Mechanism::try_from(12345), Err(Error::EINVAL));
+}
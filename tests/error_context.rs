@@ -0,0 +1,109 @@
+use std::io;
+
+use zmq::{Error, ErrorContext, Operation, ResultExt};
+
+#[test]
+fn bind_error_context() {
+    let ctx = zmq::Context::new();
+    let first = ctx.socket(zmq::REP).unwrap();
+    first.bind("tcp://127.0.0.1:*").unwrap();
+    let endpoint = first.get_last_endpoint().unwrap().unwrap();
+
+    let second = ctx.socket(zmq::ROUTER).unwrap();
+    let err = second
+        .bind(&endpoint)
+        .for_operation(&second, Operation::Bind(endpoint.clone()))
+        .unwrap_err();
+
+    assert_eq!(err.error(), Error::EADDRINUSE);
+    assert_eq!(err.operation(), &Operation::Bind(endpoint.clone()));
+    assert_eq!(err.socket_type(), Some(zmq::ROUTER));
+
+    let message = err.to_string();
+    assert!(message.contains(&endpoint));
+    assert!(message.contains("ROUTER"));
+    assert!(message.ends_with(Error::EADDRINUSE.message()));
+
+    let io_err: io::Error = err.clone().into();
+    assert_eq!(io_err.kind(), io::ErrorKind::AddrInUse);
+    assert_eq!(io_err.to_string(), message);
+
+    let plain: Error = err.into();
+    assert_eq!(plain, Error::EADDRINUSE);
+}
+
+#[test]
+fn sockopt_error_context() {
+    let ctx = zmq::Context::new();
+    let socket = ctx.socket(zmq::REQ).unwrap();
+    let err = socket
+        .set_sndhwm(-1)
+        .for_operation(&socket, Operation::SetSockOpt("ZMQ_SNDHWM"))
+        .unwrap_err();
+
+    assert_eq!(err.error(), Error::EINVAL);
+    assert_eq!(
+        err.to_string(),
+        format!("setting ZMQ_SNDHWM on REQ socket failed: {}", Error::EINVAL)
+    );
+    assert_eq!(
+        std::error::Error::source(&err).map(|e| e.to_string()),
+        Some(Error::EINVAL.to_string())
+    );
+}
+
+#[test]
+fn error_context_question_mark() {
+    fn recv(socket: &zmq::Socket) -> Result<Vec<u8>, ErrorContext> {
+        let msg = socket
+            .recv_bytes(zmq::DONTWAIT)
+            .for_operation(socket, Operation::RecvMessage)?;
+        Ok(msg)
+    }
+
+    let ctx = zmq::Context::new();
+    let socket = ctx.socket(zmq::PULL).unwrap();
+    let err = recv(&socket).unwrap_err();
+    assert_eq!(err.error(), Error::EAGAIN);
+    assert_eq!(err.operation(), &Operation::RecvMessage);
+}
+
+#[test]
+fn recorded_error_context() {
+    let ctx = zmq::Context::new();
+    let first = ctx.socket(zmq::PUB).unwrap();
+    first.bind("tcp://127.0.0.1:*").unwrap();
+    let endpoint = first.get_last_endpoint().unwrap().unwrap();
+
+    let socket = ctx.socket(zmq::PUB).unwrap();
+    assert_eq!(socket.last_error(), None);
+
+    let err = socket.bind(&endpoint).with_socket(&socket).unwrap_err();
+    assert_eq!(err.error(), Error::EADDRINUSE);
+    assert_eq!(err.operation(), &Operation::Bind(endpoint.clone()));
+    assert_eq!(err.socket_type(), Some(zmq::PUB));
+    assert_eq!(socket.last_error(), Some(err));
+
+    let err = socket.set_linger(-2).with_socket(&socket).unwrap_err();
+    assert_eq!(err.operation(), &Operation::SetSockOpt("ZMQ_LINGER"));
+
+    let err = socket.recv_bytes(0).with_socket(&socket).unwrap_err();
+    assert_eq!(err.error(), Error::ENOTSUP);
+    assert_eq!(err.operation(), &Operation::RecvMessage);
+
+    // Successful operations leave the last error alone.
+    socket.send("message", 0).unwrap();
+    assert_eq!(
+        socket.last_error().unwrap().operation(),
+        &Operation::RecvMessage
+    );
+
+    // Errors that were not recorded by the socket are not attributed
+    // to its last failure.
+    let err = Err::<(), _>(Error::EINTR).with_socket(&socket).unwrap_err();
+    assert_eq!(err.operation(), &Operation::Unknown);
+    assert_eq!(
+        err.to_string(),
+        format!("operation on PUB socket failed: {}", Error::EINTR)
+    );
+}
@@ -0,0 +1,97 @@
+use zmq::{Context, Socket};
+
+fn pull_push(ctx: &Context) -> (Socket, Socket) {
+    let pull = ctx.socket(zmq::PULL).unwrap();
+    pull.set_rcvtimeo(500).unwrap();
+    let push = ctx.socket(zmq::PUSH).unwrap();
+    push.set_linger(0).unwrap();
+    (pull, push)
+}
+
+fn delivered(pull: &Socket, push: &Socket) -> bool {
+    push.send("hello", 0).unwrap();
+    match pull.recv_bytes(0) {
+        Ok(msg) => msg == b"hello",
+        Err(zmq::Error::EAGAIN) => false,
+        Err(e) => panic!("unexpected error: {}", e),
+    }
+}
+
+#[test]
+fn tcp_accept_filter() {
+    let ctx = Context::new();
+
+    let (pull, push) = pull_push(&ctx);
+    pull.add_tcp_accept_filter("10.0.0.0/8").unwrap();
+    let addr = pull.bind_ephemeral("127.0.0.1").unwrap();
+    push.connect(zmq::Endpoint::from(addr).as_str()).unwrap();
+    assert!(!delivered(&pull, &push));
+
+    let (pull, push) = pull_push(&ctx);
+    pull.add_tcp_accept_filter("10.0.0.0/8").unwrap();
+    pull.add_tcp_accept_filter("127.0.0.0/8").unwrap();
+    let addr = pull.bind_ephemeral("127.0.0.1").unwrap();
+    push.connect(zmq::Endpoint::from(addr).as_str()).unwrap();
+    assert!(delivered(&pull, &push));
+
+    let (pull, push) = pull_push(&ctx);
+    pull.add_tcp_accept_filter("10.0.0.0/8").unwrap();
+    pull.clear_tcp_accept_filters().unwrap();
+    let addr = pull.bind_ephemeral("127.0.0.1").unwrap();
+    push.connect(zmq::Endpoint::from(addr).as_str()).unwrap();
+    assert!(delivered(&pull, &push));
+
+    let (pull, _) = pull_push(&ctx);
+    assert_eq!(
+        pull.add_tcp_accept_filter("127.0.0.1/33"),
+        Err(zmq::Error::EINVAL)
+    );
+    assert_eq!(pull.add_tcp_accept_filter("::1"), Err(zmq::Error::EINVAL));
+    pull.set_ipv6(true).unwrap();
+    pull.add_tcp_accept_filter("::1/128").unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn ipc_filters() {
+    let dir = tempfile::tempdir().unwrap();
+    let ctx = Context::new();
+    let uid = unsafe { libc::getuid() };
+    let pid = std::process::id() as libc::pid_t;
+
+    let check = |name: &str, setup: &dyn Fn(&Socket)| {
+        let endpoint = format!("ipc://{}", dir.path().join(name).display());
+        let (pull, push) = pull_push(&ctx);
+        setup(&pull);
+        pull.bind(&endpoint).unwrap();
+        push.connect(&endpoint).unwrap();
+        delivered(&pull, &push)
+    };
+
+    // Whether IPC filters are supported at all depends on how libzmq
+    // was built.
+    let socket = ctx.socket(zmq::PULL).unwrap();
+    if socket.add_ipc_uid_filter(uid) == Err(zmq::Error::EINVAL) {
+        assert_eq!(socket.add_ipc_gid_filter(0), Err(zmq::Error::EINVAL));
+        assert_eq!(socket.clear_ipc_uid_filters(), Err(zmq::Error::EINVAL));
+        return;
+    }
+
+    assert!(check("own-uid", &|s| s.add_ipc_uid_filter(uid).unwrap()));
+    assert!(!check("other-uid", &|s| s
+        .add_ipc_uid_filter(uid.wrapping_add(1))
+        .unwrap()));
+    // PID filters need `SO_PEERCRED`, which is not always available.
+    if socket.add_ipc_pid_filter(pid) != Err(zmq::Error::EINVAL) {
+        assert!(check("own-pid", &|s| s.add_ipc_pid_filter(pid).unwrap()));
+    }
+    assert!(!check("other-gid", &|s| {
+        s.add_ipc_gid_filter(unsafe { libc::getgid() }.wrapping_add(12345))
+            .unwrap()
+    }));
+    assert!(check("cleared", &|s| {
+        s.add_ipc_uid_filter(uid.wrapping_add(1)).unwrap();
+        s.clear_ipc_uid_filters().unwrap();
+        s.clear_ipc_gid_filters().unwrap();
+    }));
+}
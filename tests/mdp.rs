@@ -0,0 +1,236 @@
+#[macro_use]
+mod common;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use zmq::mdp::{Broker, Client, PipelinedClient, Request, Worker};
+use zmq::Context;
+
+const HEARTBEAT: Duration = Duration::from_millis(50);
+
+fn start_broker(
+    ctx: &Context,
+    endpoint: &str,
+    heartbeat: Duration,
+) -> thread::JoinHandle<zmq::Error> {
+    let mut broker = Broker::new(ctx).unwrap();
+    broker.set_heartbeat_interval(heartbeat);
+    broker.bind(endpoint).unwrap();
+    thread::spawn(move || broker.run().unwrap_err())
+}
+
+fn worker(ctx: &Context, endpoint: &str, service: &str) -> Worker {
+    let mut worker = Worker::new(ctx, endpoint, service).unwrap();
+    worker.set_heartbeat_interval(HEARTBEAT);
+    worker.set_reconnect_interval(HEARTBEAT);
+    worker
+}
+
+/// Serve requests on a background thread until the context is shut
+/// down.
+fn start_worker<F>(mut worker: Worker, handler: F) -> thread::JoinHandle<zmq::Error>
+where
+    F: Fn(&Worker, Request) -> zmq::Result<()> + Send + 'static,
+{
+    thread::spawn(move || loop {
+        let result = worker.recv().and_then(|request| handler(&worker, request));
+        if let Err(e) = result {
+            return e;
+        }
+    })
+}
+
+fn echo(worker: &Worker, request: Request) -> zmq::Result<()> {
+    worker.reply(&request, request.body())
+}
+
+fn mmi_service(client: &mut Client, service: &str) -> Vec<u8> {
+    client.send("mmi.service", &[service]).unwrap().remove(0)
+}
+
+test!(test_echo, {
+    let ctx = Context::new();
+    let broker = start_broker(&ctx, "inproc://mdp-echo", HEARTBEAT);
+    let worker = start_worker(worker(&ctx, "inproc://mdp-echo", "echo"), echo);
+
+    let mut client = Client::new(&ctx, "inproc://mdp-echo").unwrap();
+    for _ in 0..3 {
+        assert_eq!(
+            client.send("echo", &["hello", "world"]).unwrap(),
+            vec![b"hello", b"world"]
+        );
+    }
+
+    ctx.shutdown().unwrap();
+    assert_eq!(broker.join().unwrap(), zmq::Error::ETERM);
+    assert_eq!(worker.join().unwrap(), zmq::Error::ETERM);
+});
+
+test!(test_mmi, {
+    let ctx = Context::new();
+    let _broker = start_broker(&ctx, "inproc://mdp-mmi", HEARTBEAT);
+    let _worker = start_worker(worker(&ctx, "inproc://mdp-mmi", "echo"), echo);
+
+    let mut client = Client::new(&ctx, "inproc://mdp-mmi").unwrap();
+    // Wait for the worker to be registered.
+    client.send("echo", &["ping"]).unwrap();
+    assert_eq!(mmi_service(&mut client, "echo"), b"200");
+    assert_eq!(mmi_service(&mut client, "unknown"), b"404");
+    assert_eq!(client.send("mmi.unknown", &[""]).unwrap(), vec![b"501"]);
+
+    ctx.shutdown().unwrap();
+});
+
+test!(test_pipelined_client_partial_replies, {
+    let ctx = Context::new();
+    let _broker = start_broker(&ctx, "inproc://mdp-partial", HEARTBEAT);
+    let _worker = start_worker(
+        worker(&ctx, "inproc://mdp-partial", "split"),
+        |worker, request| {
+            for frame in request.body() {
+                worker.reply_partial(&request, [frame])?;
+            }
+            worker.reply(&request, ["done"])
+        },
+    );
+
+    let client = PipelinedClient::new(&ctx, "inproc://mdp-partial").unwrap();
+    client.send("split", &["a", "b"]).unwrap();
+    client.send("split", &["c"]).unwrap();
+
+    let mut replies = Vec::new();
+    while replies.len() < 5 {
+        let reply = client.recv().unwrap();
+        assert_eq!(reply.service(), b"split");
+        replies.push((reply.body().concat(), reply.is_final()));
+    }
+    let expected: Vec<(Vec<u8>, bool)> = vec![
+        (b"a".to_vec(), false),
+        (b"b".to_vec(), false),
+        (b"done".to_vec(), true),
+        (b"c".to_vec(), false),
+        (b"done".to_vec(), true),
+    ];
+    assert_eq!(replies, expected);
+
+    ctx.shutdown().unwrap();
+});
+
+test!(test_busy_worker_expires, {
+    let ctx = Context::new();
+    let _broker = start_broker(&ctx, "inproc://mdp-busy", HEARTBEAT);
+    let mut stuck = worker(&ctx, "inproc://mdp-busy", "echo");
+    let client = PipelinedClient::new(&ctx, "inproc://mdp-busy").unwrap();
+    client.send("echo", &["hello"]).unwrap();
+
+    // The worker takes the request, and then falls silent.
+    assert_eq!(stuck.recv().unwrap().into_body(), vec![b"hello"]);
+    let mut mmi = Client::new(&ctx, "inproc://mdp-busy").unwrap();
+    while mmi_service(&mut mmi, "echo") != b"404" {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // The request is passed on to the next worker.
+    let _worker = start_worker(worker(&ctx, "inproc://mdp-busy", "echo"), echo);
+    assert_eq!(client.recv().unwrap().into_body(), vec![b"hello"]);
+
+    ctx.shutdown().unwrap();
+});
+
+test!(test_worker_ignores_malformed_request, {
+    let ctx = Context::new();
+    let broker = ctx.socket(zmq::ROUTER).unwrap();
+    broker.bind("inproc://mdp-malformed").unwrap();
+    let mut worker = worker(&ctx, "inproc://mdp-malformed", "echo");
+    let ready = broker.recv_multipart(0).unwrap();
+    let identity = &ready[0][..];
+
+    // Without the empty delimiter, the body would be shifted.
+    let malformed = [
+        identity,
+        zmq::mdp::WORKER.as_bytes(),
+        b"\x02",
+        b"client",
+        b"oops",
+    ];
+    broker.send_multipart(malformed, 0).unwrap();
+    let valid = [
+        identity,
+        zmq::mdp::WORKER.as_bytes(),
+        b"\x02",
+        b"client",
+        b"",
+        b"body",
+    ];
+    broker.send_multipart(valid, 0).unwrap();
+    assert_eq!(worker.recv().unwrap().into_body(), vec![b"body"]);
+});
+
+test!(test_client_timeout, {
+    let ctx = Context::new();
+    let _broker = start_broker(&ctx, "inproc://mdp-timeout", HEARTBEAT);
+
+    let mut client = Client::new(&ctx, "inproc://mdp-timeout").unwrap();
+    client.set_timeout(Duration::from_millis(50));
+    client.set_retries(2);
+    assert_eq!(
+        client.send("nobody", &["hello"]),
+        Err(zmq::Error::ETIMEDOUT)
+    );
+
+    let mut client = PipelinedClient::new(&ctx, "inproc://mdp-timeout").unwrap();
+    client.set_timeout(Duration::from_millis(50));
+    client.send("nobody", &["hello"]).unwrap();
+    assert_eq!(client.recv(), Err(zmq::Error::ETIMEDOUT));
+
+    ctx.shutdown().unwrap();
+});
+
+test!(test_worker_disconnects_on_drop, {
+    let ctx = Context::new();
+    // With the default heartbeat, the broker would take seconds to
+    // notice a silent worker.
+    let _broker = start_broker(&ctx, "inproc://mdp-drop", Duration::from_millis(2500));
+    let mut client = Client::new(&ctx, "inproc://mdp-drop").unwrap();
+
+    let worker = Worker::new(&ctx, "inproc://mdp-drop", "echo").unwrap();
+    while mmi_service(&mut client, "echo") != b"200" {
+        thread::sleep(Duration::from_millis(10));
+    }
+    drop(worker);
+    let dropped = Instant::now();
+    while mmi_service(&mut client, "echo") != b"404" {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(dropped.elapsed() < Duration::from_secs(1));
+
+    ctx.shutdown().unwrap();
+});
+
+test!(test_worker_reconnects, {
+    let mut broker_ctx = Context::new();
+    let mut broker = Broker::new(&broker_ctx).unwrap();
+    broker.set_heartbeat_interval(HEARTBEAT);
+    let addr = broker.get_ref().bind_ephemeral("127.0.0.1").unwrap();
+    let endpoint = format!("tcp://{}", addr);
+    let first_broker = thread::spawn(move || broker.run().unwrap_err());
+
+    let ctx = Context::new();
+    let _worker = start_worker(worker(&ctx, &endpoint, "echo"), echo);
+    let mut client = Client::new(&ctx, &endpoint).unwrap();
+    assert_eq!(client.send("echo", &["one"]).unwrap(), vec![b"one"]);
+
+    // Replace the broker; the worker has to notice the missing
+    // heartbeats and register with the new one.
+    broker_ctx.shutdown().unwrap();
+    assert_eq!(first_broker.join().unwrap(), zmq::Error::ETERM);
+    // Terminating the context releases the port for the new broker.
+    broker_ctx.destroy().unwrap();
+    let broker_ctx = Context::new();
+    let _broker = start_broker(&broker_ctx, &endpoint, HEARTBEAT);
+    assert_eq!(client.send("echo", &["two"]).unwrap(), vec![b"two"]);
+
+    ctx.shutdown().unwrap();
+    broker_ctx.shutdown().unwrap();
+});
@@ -0,0 +1,74 @@
+#![cfg(all(unix, feature = "mio"))]
+
+use std::time::{Duration, Instant};
+
+use mio::{Events, Interest, Poll, Token};
+use zmq::EventedSocket;
+
+#[test]
+fn evented_socket_recheck() {
+    let ctx = zmq::Context::new();
+    let mut pull = EventedSocket::new(ctx.socket(zmq::PULL).unwrap());
+    pull.get_ref().bind("inproc://evented").unwrap();
+    let push = ctx.socket(zmq::PUSH).unwrap();
+    push.connect("inproc://evented").unwrap();
+
+    let mut poll = Poll::new().unwrap();
+    let mut events = Events::with_capacity(8);
+    poll.registry()
+        .register(&mut pull, Token(7), Interest::READABLE)
+        .unwrap();
+    assert!(pull.needs_recheck());
+    assert!(!pull.ready().unwrap().contains(zmq::POLLIN));
+    assert!(!pull.needs_recheck());
+
+    // Queue two messages at once; only a single edge is signalled.
+    push.send("one", 0).unwrap();
+    push.send("two", 0).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut received = Vec::new();
+    while received.len() < 2 {
+        assert!(Instant::now() < deadline, "timed out");
+        if !pull.needs_recheck() {
+            poll.poll(&mut events, Some(Duration::from_millis(100)))
+                .unwrap();
+            if events.iter().all(|event| event.token() != Token(7)) {
+                continue;
+            }
+        }
+        while pull.ready().unwrap().contains(zmq::POLLIN) {
+            received.push(pull.recv_bytes(0).unwrap());
+        }
+    }
+    assert_eq!(received, vec![b"one".to_vec(), b"two".to_vec()]);
+    assert!(!pull.needs_recheck());
+
+    poll.registry().deregister(&mut pull).unwrap();
+}
+
+#[test]
+fn evented_socket_multipart_and_strings() {
+    let ctx = zmq::Context::new();
+    let (a, b) = ctx.pipe().unwrap();
+    let a = EventedSocket::new(a);
+    let b = EventedSocket::new(b);
+    assert!(a.ready().unwrap().contains(zmq::POLLOUT));
+
+    a.send_multipart(["one", "two"], 0).unwrap();
+    assert!(a.needs_recheck());
+    assert_eq!(
+        b.recv_multipart(0).unwrap(),
+        vec![b"one".to_vec(), b"two".to_vec()]
+    );
+
+    a.send("three", 0).unwrap();
+    b.ready().unwrap();
+    assert_eq!(b.recv_string(0).unwrap().unwrap(), "three");
+    assert!(b.needs_recheck());
+
+    // The socket may be used through the reference without tracking.
+    b.ready().unwrap();
+    b.get_ref().send("four", 0).unwrap();
+    assert!(b.needs_recheck());
+}
@@ -0,0 +1,153 @@
+#[macro_use]
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+use zmq::pirate::{Client, Queue, Worker, READY};
+use zmq::Context;
+
+const HEARTBEAT: Duration = Duration::from_millis(50);
+
+fn queue(ctx: &Context) -> Queue {
+    let mut queue = Queue::new(ctx).unwrap();
+    queue.set_heartbeat_interval(HEARTBEAT).unwrap();
+    queue
+}
+
+fn start_queue(ctx: &Context, frontend: &str, backend: &str) -> thread::JoinHandle<zmq::Error> {
+    let mut queue = queue(ctx);
+    queue.bind_frontend(frontend).unwrap();
+    queue.bind_backend(backend).unwrap();
+    thread::spawn(move || queue.run().unwrap_err())
+}
+
+/// Echo requests on a background thread until the context is shut
+/// down.
+fn start_worker(ctx: &Context, endpoint: &str) -> thread::JoinHandle<zmq::Error> {
+    let mut worker = Worker::new(ctx, endpoint).unwrap();
+    worker.set_heartbeat_interval(HEARTBEAT);
+    worker.set_reconnect_interval(HEARTBEAT);
+    thread::spawn(move || loop {
+        let result = worker
+            .recv()
+            .and_then(|request| worker.reply(&request, request.body()));
+        if let Err(e) = result {
+            return e;
+        }
+    })
+}
+
+test!(test_echo, {
+    let ctx = Context::new();
+    let queue = start_queue(&ctx, "inproc://pirate-front", "inproc://pirate-back");
+    let workers: Vec<_> = (0..2)
+        .map(|_| start_worker(&ctx, "inproc://pirate-back"))
+        .collect();
+
+    let mut client = Client::new(&ctx, "inproc://pirate-front").unwrap();
+    for _ in 0..4 {
+        assert_eq!(
+            client.send(&["hello", "world"]).unwrap(),
+            vec![b"hello", b"world"]
+        );
+    }
+
+    ctx.shutdown().unwrap();
+    assert_eq!(queue.join().unwrap(), zmq::Error::ETERM);
+    for worker in workers {
+        assert_eq!(worker.join().unwrap(), zmq::Error::ETERM);
+    }
+});
+
+test!(test_queue_expires_silent_workers, {
+    let ctx = Context::new();
+    let _queue = start_queue(
+        &ctx,
+        "inproc://pirate-expire-front",
+        "inproc://pirate-expire-back",
+    );
+
+    // A worker that signals it is ready, but never sends heartbeats nor
+    // answers requests. It is first in line until it expires.
+    let silent = ctx.socket(zmq::DEALER).unwrap();
+    silent.connect("inproc://pirate-expire-back").unwrap();
+    silent.send(READY, 0).unwrap();
+    thread::sleep(HEARTBEAT);
+    let _worker = start_worker(&ctx, "inproc://pirate-expire-back");
+    thread::sleep(HEARTBEAT * 5);
+
+    let mut client = Client::new(&ctx, "inproc://pirate-expire-front").unwrap();
+    client.set_timeout(Duration::from_millis(500));
+    client.set_retries(1);
+    assert_eq!(client.send(&["hello"]).unwrap(), vec![b"hello"]);
+
+    ctx.shutdown().unwrap();
+});
+
+test!(test_worker_reconnects, {
+    let mut queue_ctx = Context::new();
+    let queue = queue(&queue_ctx);
+    queue.bind_frontend("inproc://pirate-reconnect").unwrap();
+    let addr = queue.get_backend_ref().bind_ephemeral("127.0.0.1").unwrap();
+    let backend = format!("tcp://{}", addr);
+    let first_queue = thread::spawn(move || { queue }.run().unwrap_err());
+
+    let ctx = Context::new();
+    let _worker = start_worker(&ctx, &backend);
+    let mut client = Client::new(&queue_ctx, "inproc://pirate-reconnect").unwrap();
+    assert_eq!(client.send(&["one"]).unwrap(), vec![b"one"]);
+
+    // Replace the queue; the worker has to notice the missing heartbeats
+    // and signal that it is ready to the new one.
+    drop(client);
+    queue_ctx.shutdown().unwrap();
+    assert_eq!(first_queue.join().unwrap(), zmq::Error::ETERM);
+    // Wait for the sockets to be closed, so the address can be reused.
+    queue_ctx.destroy().unwrap();
+    let queue_ctx = Context::new();
+    let _queue = start_queue(&queue_ctx, "inproc://pirate-reconnect", &backend);
+    let mut client = Client::new(&queue_ctx, "inproc://pirate-reconnect").unwrap();
+    assert_eq!(client.send(&["two"]).unwrap(), vec![b"two"]);
+
+    ctx.shutdown().unwrap();
+    queue_ctx.shutdown().unwrap();
+});
+
+test!(test_client_retries, {
+    let ctx = Context::new();
+    let server = ctx.socket(zmq::ROUTER).unwrap();
+    server.bind("inproc://pirate-retry").unwrap();
+    // Ignore the first request, and answer the second.
+    let server = thread::spawn(move || {
+        server.recv_multipart(0).unwrap();
+        let mut request = server.recv_multipart(0).unwrap();
+        request.push(b"pong".to_vec());
+        server.send_multipart(request, 0).unwrap();
+        server
+    });
+
+    let mut client = Client::new(&ctx, "inproc://pirate-retry").unwrap();
+    client.set_timeout(Duration::from_millis(50));
+    assert_eq!(client.send(&["ping"]).unwrap(), vec![b"ping", b"pong"]);
+    // Keep the server connected, but silent.
+    let _server = server.join().unwrap();
+    client.set_retries(2);
+    assert_eq!(client.send(&["ping"]), Err(zmq::Error::ETIMEDOUT));
+
+    ctx.shutdown().unwrap();
+});
+
+test!(test_worker_zero_liveness, {
+    let ctx = Context::new();
+    let mut worker = Worker::new(&ctx, "inproc://pirate-nobody").unwrap();
+    worker.set_heartbeat_interval(Duration::from_millis(10));
+    worker.set_heartbeat_liveness(0);
+    worker.set_reconnect_interval(Duration::from_millis(10));
+    let worker = thread::spawn(move || worker.recv().unwrap_err());
+
+    // The worker keeps reconnecting after every silent interval.
+    thread::sleep(Duration::from_millis(100));
+    ctx.shutdown().unwrap();
+    assert_eq!(worker.join().unwrap(), zmq::Error::ETERM);
+});
@@ -2,29 +2,64 @@
 // arbitrary FDs.
 
 use nix::unistd;
-use std::os::unix::io::RawFd;
+use std::os::unix::io::{AsFd, AsRawFd, FromRawFd, OwnedFd, RawFd};
 use std::thread;
 
 #[test]
 fn test_pipe_poll() {
     let (pipe_read, pipe_write) = unistd::pipe().expect("pipe creation failed");
+    let pipe_read = unsafe { OwnedFd::from_raw_fd(pipe_read) };
     let writer_thread = thread::spawn(move || {
         pipe_writer(pipe_write);
     });
-    let pipe_item = zmq::PollItem::from_fd(pipe_read, zmq::POLLIN);
-    assert!(pipe_item.has_fd(pipe_read));
+    let pipe_item = zmq::PollItem::from_borrowed_fd(pipe_read.as_fd(), zmq::POLLIN);
+    assert!(pipe_item.has_fd(pipe_read.as_raw_fd()));
 
     let mut poll_items = [pipe_item];
     assert_eq!(zmq::poll(&mut poll_items, 1000).unwrap(), 1);
     assert_eq!(poll_items[0].get_revents(), zmq::POLLIN);
 
     let mut buf = vec![0];
-    assert_eq!(unistd::read(pipe_read, &mut buf).unwrap(), 1);
+    assert_eq!(unistd::read(pipe_read.as_raw_fd(), &mut buf).unwrap(), 1);
     assert_eq!(buf, b"X");
 
     writer_thread.join().unwrap();
 }
 
+#[test]
+#[allow(deprecated)]
+fn test_pipe_poll_raw_fd() {
+    let (pipe_read, pipe_write) = unistd::pipe().expect("pipe creation failed");
+    pipe_writer(pipe_write);
+    let pipe_item = zmq::PollItem::from_fd(pipe_read, zmq::POLLIN);
+    assert!(pipe_item.has_fd(pipe_read));
+
+    let mut poll_items = [pipe_item];
+    assert_eq!(zmq::poll(&mut poll_items, 1000).unwrap(), 1);
+    assert_eq!(poll_items[0].get_revents(), zmq::POLLIN);
+    unistd::close(pipe_read).unwrap();
+}
+
+#[test]
+fn test_socket_as_fd() {
+    let ctx = zmq::Context::new();
+    let socket = ctx.socket(zmq::PAIR).unwrap();
+    let fd = socket.as_fd();
+    assert_eq!(fd.as_raw_fd(), socket.get_fd().unwrap());
+
+    let mut poll_items = [zmq::PollItem::from_borrowed_fd(fd, zmq::POLLIN)];
+    zmq::poll(&mut poll_items, 0).unwrap();
+
+    // The descriptor is still available once libzmq refuses to report
+    // it, until the socket is closed. libzmq only notices the shutdown
+    // when the socket is used.
+    let raw_fd = socket.as_raw_fd();
+    ctx.shutdown().unwrap();
+    assert_eq!(socket.recv_bytes(zmq::DONTWAIT), Err(zmq::Error::ETERM));
+    assert_eq!(socket.get_fd(), Err(zmq::Error::ETERM));
+    assert_eq!(socket.as_fd().as_raw_fd(), raw_fd);
+}
+
 fn pipe_writer(fd: RawFd) {
     unistd::write(fd, b"X").expect("pipe write failed");
 }
@@ -0,0 +1,240 @@
+#[macro_use]
+mod common;
+
+use std::thread;
+
+use zmq::{Context, Proxy, ProxyAction, ProxyStats};
+
+/// Run `proxy` on a frontend and backend bound to `name`, until the
+/// context is shut down.
+fn spawn_proxy<F>(
+    ctx: &Context,
+    name: &str,
+    frontend: zmq::SocketType,
+    backend: zmq::SocketType,
+    proxy: F,
+) -> thread::JoinHandle<zmq::Error>
+where
+    F: FnOnce(&zmq::Socket, &zmq::Socket) -> zmq::Result<()> + Send + 'static,
+{
+    let frontend = ctx.socket(frontend).unwrap();
+    frontend.bind(&format!("inproc://{}-front", name)).unwrap();
+    let backend = ctx.socket(backend).unwrap();
+    backend.bind(&format!("inproc://{}-back", name)).unwrap();
+    thread::spawn(move || proxy(&frontend, &backend).unwrap_err())
+}
+
+test!(test_hooks_modify_and_drop, {
+    let ctx = Context::new();
+    let proxy = spawn_proxy(&ctx, "modify", zmq::PULL, zmq::PUSH, |frontend, backend| {
+        Proxy::new(frontend, backend)
+            .on_frontend_msg(|msg| {
+                if msg[0].starts_with(b"private.") {
+                    return ProxyAction::Drop;
+                }
+                msg.push(zmq::Message::from("checked"));
+                ProxyAction::Pass
+            })
+            .run()
+    });
+
+    let push = ctx.socket(zmq::PUSH).unwrap();
+    push.connect("inproc://modify-front").unwrap();
+    let pull = ctx.socket(zmq::PULL).unwrap();
+    pull.connect("inproc://modify-back").unwrap();
+
+    push.send_multipart(["private.key", "secret"], 0).unwrap();
+    push.send_multipart(["public.news", "hello"], 0).unwrap();
+    assert_eq!(
+        pull.recv_multipart(0).unwrap(),
+        vec![&b"public.news"[..], b"hello", b"checked"]
+    );
+    // The dropped message was not just delayed.
+    assert_eq!(pull.poll(zmq::POLLIN, 100).unwrap(), 0);
+
+    ctx.shutdown().unwrap();
+    assert_eq!(proxy.join().unwrap(), zmq::Error::ETERM);
+});
+
+test!(test_hook_redirect, {
+    let ctx = Context::new();
+    let proxy = spawn_proxy(
+        &ctx,
+        "redirect",
+        zmq::ROUTER,
+        zmq::DEALER,
+        |frontend, backend| {
+            Proxy::new(frontend, backend)
+                .on_frontend_msg(|msg| {
+                    // The request body follows the routing envelope.
+                    if &msg.last().unwrap()[..] == b"denied" {
+                        *msg.last_mut().unwrap() = zmq::Message::from("forbidden");
+                        return ProxyAction::Redirect;
+                    }
+                    ProxyAction::Pass
+                })
+                .run()
+        },
+    );
+
+    let rep = ctx.socket(zmq::REP).unwrap();
+    rep.connect("inproc://redirect-back").unwrap();
+    let req = ctx.socket(zmq::REQ).unwrap();
+    req.connect("inproc://redirect-front").unwrap();
+
+    req.send("denied", 0).unwrap();
+    assert_eq!(req.recv_bytes(0).unwrap(), b"forbidden");
+
+    req.send("allowed", 0).unwrap();
+    assert_eq!(rep.recv_bytes(0).unwrap(), b"allowed");
+    rep.send("ok", 0).unwrap();
+    assert_eq!(req.recv_bytes(0).unwrap(), b"ok");
+
+    ctx.shutdown().unwrap();
+    assert_eq!(proxy.join().unwrap(), zmq::Error::ETERM);
+});
+
+test!(test_capture, {
+    let ctx = Context::new();
+    let capture = ctx.socket(zmq::PULL).unwrap();
+    capture.bind("inproc://capture").unwrap();
+    let capture_ctx = ctx.clone();
+    let proxy = spawn_proxy(
+        &ctx,
+        "capture",
+        zmq::ROUTER,
+        zmq::DEALER,
+        move |frontend, backend| {
+            let capture = capture_ctx.socket(zmq::PUSH).unwrap();
+            capture.connect("inproc://capture").unwrap();
+            Proxy::new(frontend, backend)
+                .capture(&capture)
+                .on_backend_msg(|_| ProxyAction::Drop)
+                .run()
+        },
+    );
+
+    let rep = ctx.socket(zmq::REP).unwrap();
+    rep.connect("inproc://capture-back").unwrap();
+    let req = ctx.socket(zmq::REQ).unwrap();
+    req.connect("inproc://capture-front").unwrap();
+
+    req.send("request", 0).unwrap();
+    assert_eq!(rep.recv_bytes(0).unwrap(), b"request");
+    rep.send("reply", 0).unwrap();
+
+    // Both directions are captured, including the dropped reply.
+    let request = capture.recv_multipart(0).unwrap();
+    assert_eq!(request.last().unwrap(), b"request");
+    let reply = capture.recv_multipart(0).unwrap();
+    assert_eq!(reply.last().unwrap(), b"reply");
+    assert_eq!(req.poll(zmq::POLLIN, 100).unwrap(), 0);
+
+    ctx.shutdown().unwrap();
+    assert_eq!(proxy.join().unwrap(), zmq::Error::ETERM);
+});
+
+test!(test_steerable_controller, {
+    let ctx = Context::new();
+    let frontend = ctx.socket(zmq::PULL).unwrap();
+    frontend.bind("inproc://steerable-front").unwrap();
+    let backend = ctx.socket(zmq::PUSH).unwrap();
+    backend.bind("inproc://steerable-back").unwrap();
+    let controller = zmq::spawn_proxy_steerable(frontend, backend).unwrap();
+
+    let push = ctx.socket(zmq::PUSH).unwrap();
+    push.connect("inproc://steerable-front").unwrap();
+    let pull = ctx.socket(zmq::PULL).unwrap();
+    pull.connect("inproc://steerable-back").unwrap();
+
+    push.send("hello", 0).unwrap();
+    assert_eq!(pull.recv_bytes(0).unwrap(), b"hello");
+
+    controller.pause().unwrap();
+    push.send("paused", 0).unwrap();
+    assert_eq!(pull.poll(zmq::POLLIN, 100).unwrap(), 0);
+    controller.resume().unwrap();
+    assert_eq!(pull.recv_bytes(0).unwrap(), b"paused");
+
+    assert_eq!(
+        controller.statistics().unwrap(),
+        ProxyStats {
+            frontend_msgs_in: 2,
+            frontend_bytes_in: 11,
+            backend_msgs_out: 2,
+            backend_bytes_out: 11,
+            ..ProxyStats::default()
+        }
+    );
+    controller.terminate().unwrap();
+});
+
+test!(test_steerable_controller_reports_error, {
+    let ctx = Context::new();
+    let frontend = ctx.socket(zmq::PULL).unwrap();
+    let backend = ctx.socket(zmq::PUSH).unwrap();
+    let controller = zmq::spawn_proxy_steerable(frontend, backend).unwrap();
+    ctx.shutdown().unwrap();
+    assert_eq!(controller.terminate(), Err(zmq::Error::ETERM));
+});
+
+test!(test_steerable_controller_proxy_stopped, {
+    let ctx = Context::new();
+    let backend_ctx = Context::new();
+    let frontend = ctx.socket(zmq::PULL).unwrap();
+    let backend = backend_ctx.socket(zmq::PUSH).unwrap();
+    let controller = zmq::spawn_proxy_steerable(frontend, backend).unwrap();
+
+    // The proxy fails, while the controller's context stays usable.
+    backend_ctx.shutdown().unwrap();
+    // Until the proxy notices the shutdown, it keeps answering.
+    let err = loop {
+        if let Err(err) = controller.statistics() {
+            break err;
+        }
+    };
+    assert_eq!(err, zmq::Error::EAGAIN);
+    assert_eq!(controller.terminate(), Err(zmq::Error::ETERM));
+});
+
+test!(test_spawn_proxy, {
+    let ctx = Context::new();
+    let frontend = ctx.socket(zmq::ROUTER).unwrap();
+    frontend.bind("inproc://spawn-front").unwrap();
+    let backend = ctx.socket(zmq::DEALER).unwrap();
+    backend.bind("inproc://spawn-back").unwrap();
+    let proxy = zmq::spawn_proxy(frontend, backend).unwrap();
+
+    let rep = ctx.socket(zmq::REP).unwrap();
+    rep.connect("inproc://spawn-back").unwrap();
+    let req = ctx.socket(zmq::REQ).unwrap();
+    req.connect("inproc://spawn-front").unwrap();
+    req.send("ping", 0).unwrap();
+    assert_eq!(rep.recv_bytes(0).unwrap(), b"ping");
+    rep.send("pong", 0).unwrap();
+    assert_eq!(req.recv_bytes(0).unwrap(), b"pong");
+
+    proxy.join().unwrap();
+    // The proxy's sockets are closed, so the endpoints can be reused.
+    let frontend = ctx.socket(zmq::ROUTER).unwrap();
+    frontend.bind("inproc://spawn-front").unwrap();
+});
+
+test!(test_spawn_proxy_drop, {
+    let mut ctx = Context::new();
+    let frontend = ctx.socket(zmq::PULL).unwrap();
+    let backend = ctx.socket(zmq::PUSH).unwrap();
+    drop(zmq::spawn_proxy(frontend, backend).unwrap());
+    // Terminating the context would block if the proxy's sockets were
+    // still open.
+    ctx.destroy().unwrap();
+});
+
+test!(test_spawn_proxy_reports_error, {
+    let ctx = Context::new();
+    let frontend = ctx.socket(zmq::PULL).unwrap();
+    let backend = ctx.socket(zmq::PUSH).unwrap();
+    let proxy = zmq::spawn_proxy(frontend, backend).unwrap();
+    ctx.shutdown().unwrap();
+    assert_eq!(proxy.join(), Err(zmq::Error::ETERM));
+});
@@ -0,0 +1,131 @@
+#[macro_use]
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+fn registry_context() -> zmq::Context {
+    zmq::Context::builder()
+        .socket_registry(true)
+        .build()
+        .unwrap()
+}
+
+test!(test_registry_disabled_by_default, {
+    let mut ctx = zmq::Context::new();
+    assert!(ctx.live_sockets().is_none());
+    assert_eq!(
+        ctx.destroy_with_timeout(Duration::from_millis(10))
+            .unwrap_err(),
+        zmq::Error::EINVAL
+    );
+});
+
+test!(test_live_sockets, {
+    let ctx = registry_context();
+    assert!(ctx.live_sockets().unwrap().is_empty());
+
+    let server = ctx.socket(zmq::REP).unwrap();
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    let endpoint = server.get_last_endpoint().unwrap().unwrap();
+
+    let client = thread::Builder::new()
+        .name("registry-client".into())
+        .spawn({
+            let ctx = ctx.clone();
+            let endpoint = endpoint.clone();
+            move || {
+                let client = ctx.socket(zmq::REQ).unwrap();
+                client.connect(&endpoint).unwrap();
+                client
+            }
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let mut live = ctx.live_sockets().unwrap();
+    live.sort_by_key(|info| info.socket_type() == zmq::REQ);
+    assert_eq!(live.len(), 2);
+
+    assert_eq!(live[0].socket_type(), zmq::REP);
+    assert_eq!(live[0].bound_endpoints(), [endpoint.as_str()]);
+    assert_eq!(server.bound_endpoints()[0].as_str(), endpoint);
+    assert!(live[0].connected_endpoints().is_empty());
+    assert_eq!(live[0].thread_id(), thread::current().id());
+
+    assert_eq!(live[1].socket_type(), zmq::REQ);
+    assert_eq!(live[1].connected_endpoints(), [endpoint.as_str()]);
+    assert_eq!(live[1].thread_name(), Some("registry-client"));
+    assert!(live[1].to_string().contains("registry-client"));
+
+    client.disconnect(&endpoint).unwrap();
+    server.unbind(&endpoint).unwrap();
+    for info in ctx.live_sockets().unwrap() {
+        assert!(info.bound_endpoints().is_empty());
+        assert!(info.connected_endpoints().is_empty());
+    }
+
+    drop(client);
+    let live = ctx.live_sockets().unwrap();
+    assert_eq!(live.len(), 1);
+    assert_eq!(live[0].socket_type(), zmq::REP);
+
+    // Sockets converted into raw pointers are no longer tracked.
+    let raw = server.into_raw();
+    assert!(ctx.live_sockets().unwrap().is_empty());
+    drop(unsafe { zmq::Socket::from_raw(raw) });
+});
+
+test!(test_destroy_with_timeout, {
+    let mut ctx = registry_context();
+    let socket = ctx.socket(zmq::PUSH).unwrap();
+    socket.bind("inproc://destroy-with-timeout").unwrap();
+
+    let leaked = ctx
+        .destroy_with_timeout(Duration::from_millis(10))
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(leaked.len(), 1);
+    assert_eq!(leaked[0].socket_type(), zmq::PUSH);
+    assert_eq!(
+        leaked[0].bound_endpoints(),
+        ["inproc://destroy-with-timeout"]
+    );
+
+    let closer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        socket.set_linger(0).unwrap();
+        drop(socket);
+    });
+    assert!(ctx
+        .destroy_with_timeout(Duration::from_secs(5))
+        .unwrap()
+        .is_ok());
+    closer.join().unwrap();
+
+    assert_eq!(ctx.socket(zmq::PUSH).err(), Some(zmq::Error::ETERM));
+});
+
+test!(test_destroy_with_timeout_refuses_sockets, {
+    let mut ctx = registry_context();
+    let socket = ctx.socket(zmq::PUSH).unwrap();
+    let creator = thread::spawn({
+        let ctx = ctx.clone();
+        move || {
+            // Wait for the context to start waiting for `socket`.
+            while ctx.get_io_threads().is_ok() {
+                thread::sleep(Duration::from_millis(5));
+            }
+            // A socket created now would not be waited for.
+            let result = ctx.socket(zmq::PUSH).err();
+            drop(socket);
+            result
+        }
+    });
+    assert!(ctx
+        .destroy_with_timeout(Duration::from_secs(5))
+        .unwrap()
+        .is_ok());
+    assert_eq!(creator.join().unwrap(), Some(zmq::Error::ETERM));
+});
@@ -0,0 +1,43 @@
+#![cfg(unix)]
+
+use std::net::TcpListener;
+use std::os::unix::io::{IntoRawFd, OwnedFd};
+
+#[test]
+fn bind_fd() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let ctx = zmq::Context::new();
+    let server = ctx.socket(zmq::PAIR).unwrap();
+    server
+        .bind_fd(OwnedFd::from(listener), zmq::Endpoint::from(addr).as_str())
+        .unwrap();
+    assert_eq!(server.local_endpoints(), vec![addr]);
+
+    // The fd is only used for a single bind.
+    let other = server.bind_ephemeral("127.0.0.1").unwrap();
+    assert_ne!(other, addr);
+
+    let client = ctx.socket(zmq::PAIR).unwrap();
+    client.connect(zmq::Endpoint::from(addr).as_str()).unwrap();
+    client.send("hello", 0).unwrap();
+    assert_eq!(server.recv_string(0).unwrap().unwrap(), "hello");
+}
+
+#[test]
+fn bind_raw_fd() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let ctx = zmq::Context::new();
+    let server = ctx.socket(zmq::PAIR).unwrap();
+    let endpoint = zmq::Endpoint::from(addr);
+    unsafe { server.bind_raw_fd(listener.into_raw_fd(), endpoint.as_str()) }.unwrap();
+    assert_eq!(server.local_endpoints(), vec![addr]);
+
+    let client = ctx.socket(zmq::PAIR).unwrap();
+    client.connect(endpoint.as_str()).unwrap();
+    client.send("hello", 0).unwrap();
+    assert_eq!(server.recv_string(0).unwrap().unwrap(), "hello");
+}
@@ -0,0 +1,36 @@
+#![cfg(unix)]
+
+//! `listen_fds()` reads the process environment, which the test has to
+//! modify, and that is not thread-safe, so this test lives in a binary
+//! of its own. Don't add other tests here, as they would run
+//! concurrently.
+
+use std::env;
+use std::io;
+use std::process;
+
+#[test]
+fn listen_fds() {
+    assert!(zmq::listen_fds().unwrap().is_empty());
+
+    // Descriptors meant for another process are ignored.
+    env::set_var("LISTEN_PID", (process::id() + 1).to_string());
+    env::set_var("LISTEN_FDS", "1");
+    assert!(zmq::listen_fds().unwrap().is_empty());
+    // The environment is left alone.
+    assert_eq!(env::var("LISTEN_FDS").unwrap(), "1");
+
+    env::set_var("LISTEN_PID", process::id().to_string());
+    env::set_var("LISTEN_FDS", "many");
+    assert_eq!(
+        zmq::listen_fds().unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+
+    env::set_var("LISTEN_FDS", "0");
+    assert!(zmq::listen_fds().unwrap().is_empty());
+    assert!(env::var_os("LISTEN_PID").is_some());
+    // The descriptors have been claimed, even though there were none.
+    env::set_var("LISTEN_FDS", "1");
+    assert!(zmq::listen_fds().unwrap().is_empty());
+}
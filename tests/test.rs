@@ -585,3 +585,21 @@ test!(test_getset_connect_timeout, {
         assert_eq!(sock.get_connect_timeout().unwrap(), 5000);
     }
 });
+
+test!(test_close, {
+    let (sender, receiver) = create_socketpair();
+    sender.close().unwrap();
+    receiver.close().unwrap();
+});
+
+test!(test_close_with_linger, {
+    let ctx = Context::new();
+    let sock = ctx.socket(PUSH).unwrap();
+    // Nobody will ever receive this message, so terminating the
+    // context would block forever with the default linger period.
+    sock.connect("tcp://127.0.0.1:1").unwrap();
+    sock.send("unsent", DONTWAIT).unwrap();
+    sock.close_with_linger(std::time::Duration::from_millis(10))
+        .unwrap();
+    drop(ctx);
+});
@@ -29,6 +29,12 @@ pub const ENOTSUP:          i32 = errno::ENOTSUP;
 pub const ENOBUFS:          i32 = errno::ENOBUFS;
 pub const ENETDOWN:         i32 = errno::ENETDOWN;
 pub const EADDRNOTAVAIL:    i32 = errno::EADDRNOTAVAIL;
+pub const EAFNOSUPPORT:     i32 = errno::EAFNOSUPPORT;
+pub const ENETUNREACH:      i32 = errno::ENETUNREACH;
+pub const ECONNABORTED:     i32 = errno::ECONNABORTED;
+pub const ECONNRESET:       i32 = errno::ECONNRESET;
+pub const ETIMEDOUT:        i32 = errno::ETIMEDOUT;
+pub const ENETRESET:        i32 = errno::ENETRESET;
 
 // native zmq error codes
 pub const EFSM:             i32 = ZMQ_HAUSNUMERO + 51;
@@ -179,6 +179,14 @@ pub use crate::ffi::{
 
     ZMQ_POLLITEMS_DFLT,
 
+    // These are "deprecated" in favor of ZAP authentication, but are still
+    // supported by all libzmq 4.x releases, and offer a much simpler way of
+    // restricting which peers may connect.
+    ZMQ_TCP_ACCEPT_FILTER,
+    ZMQ_IPC_FILTER_PID,
+    ZMQ_IPC_FILTER_UID,
+    ZMQ_IPC_FILTER_GID,
+
     // Undeprecated types.
     zmq_msg_t,
     zmq_free_fn,
@@ -8,9 +8,12 @@ pub mod errno {
     pub const EACCES: c_int = 13;
     pub const EADDRINUSE: c_int = 100;
     pub const EADDRNOTAVAIL: c_int = 101;
+    pub const EAFNOSUPPORT: c_int = 102;
     pub const EAGAIN: c_int = 11;
     pub const EBUSY: c_int = 16;
+    pub const ECONNABORTED: c_int = 106;
     pub const ECONNREFUSED: c_int = 107;
+    pub const ECONNRESET: c_int = 108;
     pub const EFAULT: c_int = 14;
     pub const EINTR: c_int = 4;
     pub const EHOSTUNREACH: c_int = 110;
@@ -20,6 +23,8 @@ pub mod errno {
     pub const EMSGSIZE: c_int = 115;
     pub const ENAMETOOLONG: c_int = 38;
     pub const ENETDOWN: c_int = 116;
+    pub const ENETRESET: c_int = 117;
+    pub const ENETUNREACH: c_int = 118;
     pub const ENOBUFS: c_int = 119;
     pub const ENODEV: c_int = 19;
     pub const ENOENT: c_int = 2;
@@ -29,4 +34,5 @@ pub mod errno {
     pub const ENOTSUP: c_int = 129;
     pub const EPROTO: c_int = 134;
     pub const EPROTONOSUPPORT: c_int = 135;
+    pub const ETIMEDOUT: c_int = 138;
 }